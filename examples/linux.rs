@@ -74,17 +74,12 @@ fn main() -> Result<(), std::io::Error> {
     spi.configure(&spi_options).unwrap();
 
     // Initialize device
-    let mut ads1263 = Ads1263::new(spi, cs, drdy, rst);
+    let mut ads1263 = Ads1263::probe(spi, cs, drdy, rst).expect("Failed to probe ADS1263");
     ads1263.reset(linux_embedded_hal::Delay).unwrap();
 
     // Read device id
     let id = ads1263.read_reg::<Id>().unwrap();
     println!("ID = {id:?}");
-    assert!(
-        id.dev_id() == 1,
-        "Expected ADS1263 with DEV_ID = 1, got {}",
-        id.dev_id()
-    );
 
     // Enable VBIAS
     if args.vbias {
@@ -130,7 +125,7 @@ fn main() -> Result<(), std::io::Error> {
         println!(
             "* {ch} = {}({:.5} V), status = {:?}",
             data.code,
-            data.to_voltage(2.5, 1.0),
+            data.to_voltage(),
             data.status
         );
     }
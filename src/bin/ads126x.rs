@@ -0,0 +1,560 @@
+//! Command-line tool for interactive ADS126x bring-up on Linux
+//!
+//! Talks to the device using spidev and gpio-cdev, or to a simulated ADS1263 with `--sim`.
+//! Registers are read from the device on start-up so that settings made by previous runs are kept.
+//! Voltages are converted with a 2.5 V reference, which is only right for the internal reference.
+
+use std::{
+    error::Error,
+    fmt::Debug,
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ads126x::{
+    metadata::{register, FieldInfo, GPIO_PINS},
+    registers::{GpioCon, GpioDat, GpioDir, Id, IdDev, InpMux, InpMuxMuxx, Mode2},
+    sim::SimAds126x,
+    Ads1263,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use embedded_hal::{
+    blocking::spi::{Transfer, Write as SpiWrite},
+    digital::v2::{InputPin, OutputPin},
+};
+use linux_embedded_hal::{
+    gpio_cdev::{Chip, LineRequestFlags},
+    spidev::{SpiModeFlags, SpidevOptions},
+    CdevPin, Delay, Spidev,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// DRDY polling interval in us
+const POLL_US: u32 = 100;
+
+/// Defaults are set for a Raspberry pi with Waveshare High-Precision AD HAT (see https://www.waveshare.com/wiki/High-Precision_AD_HAT).
+#[derive(Parser, Debug)]
+#[clap(name = "ads126x", version, about)]
+struct Args {
+    /// SPI interface path
+    #[clap(long, default_value = "/dev/spidev0.0")]
+    spi: String,
+
+    /// SPI clock in Hz
+    #[clap(long, default_value_t = 1_000_000)]
+    speed: u32,
+
+    /// GPIO cdev interface path
+    #[clap(long, default_value = "/dev/gpiochip0")]
+    gpio: String,
+
+    /// CS pin
+    #[clap(long, default_value_t = 22)]
+    cs: u32,
+
+    /// DRDY pin
+    #[clap(long, default_value_t = 17)]
+    drdy: u32,
+
+    /// RST pin
+    #[clap(long, default_value_t = 18)]
+    rst: u32,
+
+    /// Use a simulated ADS1263 with AINn at (n + 1) * 100 mV instead of hardware
+    #[clap(long)]
+    sim: bool,
+
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Subcommand, Debug)]
+enum Cmd {
+    /// Print device ID and revision
+    Id,
+    /// Print all registers in datasheet terms
+    Dump,
+    /// Print a register or one of its fields, e.g. `MODE2` or `MODE2.GAIN`
+    Get { name: String },
+    /// Set a register to a raw value or a field by value name or number, e.g. `MODE2.DR 400`
+    Set { name: String, value: String },
+    /// Read a channel, `P` against AINCOM or `P:N`, e.g. `AIN0` or `AIN0:AIN1`
+    Read {
+        channel: String,
+        /// Number of readings
+        #[clap(short = 'n', long, default_value_t = 1)]
+        count: u32,
+        #[clap(flatten)]
+        conv: Conversion,
+    },
+    /// Read each channel once
+    Scan {
+        channels: Vec<String>,
+        #[clap(flatten)]
+        conv: Conversion,
+    },
+    /// Continuously read a channel printing timestamp, code and voltage
+    Stream {
+        channel: String,
+        /// Stop after this many readings
+        #[clap(short = 'n', long)]
+        count: Option<u32>,
+        #[clap(flatten)]
+        conv: Conversion,
+    },
+    /// Run a calibration and print the calibration registers before and after
+    Calibrate {
+        #[clap(value_enum, default_value = "self-offset")]
+        kind: CalKind,
+        /// Calibrate ADC2 instead of ADC1
+        #[clap(long)]
+        adc2: bool,
+    },
+    /// Read the internal temperature sensor
+    Temp,
+    /// Read the analog and digital supply monitors
+    Supplies,
+    /// Show GPIOs, or drive a GPIO high/low or make it an input
+    Gpio {
+        /// GPIO number (0-7)
+        pin: Option<u8>,
+        #[clap(value_enum)]
+        state: Option<GpioState>,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct Conversion {
+    /// PGA gain, keeps the current setting if not given
+    #[clap(long)]
+    gain: Option<String>,
+
+    /// Data rate (MODE2.DR), e.g. `400` or `400 SPS`, keeps the current setting if not given
+    #[clap(long)]
+    rate: Option<String>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum CalKind {
+    /// Offset with the inputs shorted internally (SFOCAL)
+    SelfOffset,
+    /// Offset with the inputs shorted externally (SYOCAL)
+    SysOffset,
+    /// Gain with a full-scale voltage applied (SYGCAL)
+    SysGain,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum GpioState {
+    High,
+    Low,
+    Input,
+}
+
+/// Parse a field value by name, by the first word of its name (`400` for `400 SPS`) or as a number
+fn parse_value(field: &FieldInfo, s: &str) -> Result<u8> {
+    if let Some(v) = field.value(s) {
+        return Ok(v);
+    }
+    if let Some((v, _)) = field
+        .values
+        .iter()
+        .find(|(_, name)| name.split(' ').next() == Some(s))
+    {
+        return Ok(*v);
+    }
+    parse_byte(s).map_err(|_| format!("invalid value `{s}` for {}", field.name).into())
+}
+
+fn parse_byte(s: &str) -> Result<u8> {
+    let v = match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    };
+    Ok(v)
+}
+
+/// Parse a channel `P` or `P:N` into the INPMUX register
+fn parse_channel(channel: &str) -> Result<InpMux> {
+    let info = register("INPMUX").ok_or("INPMUX missing")?;
+    let (p, n) = channel.split_once(':').unwrap_or((channel, "AINCOM"));
+    let mut mux = InpMux(0);
+    mux.set_muxp(parse_value(info.field("MUXP").ok_or("MUXP missing")?, p)?);
+    mux.set_muxn(parse_value(info.field("MUXN").ok_or("MUXN missing")?, n)?);
+    Ok(mux)
+}
+
+/// Run a command against the device, writing results to `out`
+fn run<SPI, CS, DRDY, RST, SpiErr, IoErr>(
+    adc: &mut Ads1263<SPI, CS, DRDY, RST>,
+    cmd: &Cmd,
+    out: &mut impl Write,
+) -> Result<()>
+where
+    SPI: Transfer<u8, Error = SpiErr> + SpiWrite<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    SpiErr: Debug + 'static,
+    IoErr: Debug + 'static,
+{
+    adc.sync_registers()?;
+
+    match cmd {
+        Cmd::Id => {
+            let id = adc.read_reg::<Id>()?;
+            writeln!(out, "{id}")?;
+            if id.device() != Some(IdDev::Ads1263) {
+                writeln!(out, "ADC2 commands require an ADS1263")?;
+            }
+        }
+        Cmd::Dump => {
+            let regs = adc.read_registers()?;
+            writeln!(out, "{regs}")?;
+        }
+        Cmd::Get { name } => {
+            let (reg, field) = name
+                .split_once('.')
+                .map_or((name.as_str(), None), |(r, f)| (r, Some(f)));
+            let info = register(reg).ok_or_else(|| format!("unknown register `{reg}`"))?;
+            let b = adc.read_reg_at(info.address)?;
+            match field {
+                Some(field) => {
+                    let field = info
+                        .field(field)
+                        .ok_or_else(|| format!("unknown field `{field}` in {}", info.name))?;
+                    let v = field.get(b);
+                    match field.value_name(v) {
+                        Some(name) => writeln!(out, "{}.{} = {name} ({v})", info.name, field.name)?,
+                        None => writeln!(out, "{}.{} = {v}", info.name, field.name)?,
+                    }
+                }
+                None => writeln!(out, "{} = {b:02X}h: {}", info.name, info.decode(b))?,
+            }
+        }
+        Cmd::Set { name, value } => {
+            let (reg, field) = name
+                .split_once('.')
+                .map_or((name.as_str(), None), |(r, f)| (r, Some(f)));
+            let info = register(reg).ok_or_else(|| format!("unknown register `{reg}`"))?;
+            let b = match field {
+                Some(field) => {
+                    let field = info
+                        .field(field)
+                        .ok_or_else(|| format!("unknown field `{field}` in {}", info.name))?;
+                    let b = adc.read_reg_at(info.address)?;
+                    field.set(b, parse_value(field, value)?)
+                }
+                None => parse_byte(value)?,
+            };
+            adc.write_reg_at(info.address, b)?;
+            let b = adc.read_reg_at(info.address)?;
+            writeln!(out, "{} = {b:02X}h: {}", info.name, info.decode(b))?;
+        }
+        Cmd::Read {
+            channel,
+            count,
+            conv,
+        } => {
+            configure(adc, conv)?;
+            adc.write_reg(parse_channel(channel)?)?;
+            adc.start1()?;
+            for _ in 0..*count {
+                adc.wait_drdy(Delay, POLL_US)?;
+                let data = adc.read_adc1()?;
+                write!(out, "{channel}: {} ({:.7} V)", data.code, data.to_voltage())?;
+                if data.status.alarm() {
+                    write!(out, " [{}]", data.status)?;
+                }
+                writeln!(out)?;
+            }
+            adc.stop1()?;
+        }
+        Cmd::Scan { channels, conv } => {
+            configure(adc, conv)?;
+            for channel in channels {
+                adc.write_reg(parse_channel(channel)?)?;
+                adc.start1()?;
+                adc.wait_drdy(Delay, POLL_US)?;
+                let data = adc.read_adc1()?;
+                writeln!(out, "{channel}: {:.7} V", data.to_voltage())?;
+            }
+            adc.stop1()?;
+        }
+        Cmd::Stream {
+            channel,
+            count,
+            conv,
+        } => {
+            configure(adc, conv)?;
+            adc.write_reg(parse_channel(channel)?)?;
+            adc.start1()?;
+            let t0 = Instant::now();
+            let mut n = 0;
+            while count.is_none_or(|count| n < count) {
+                adc.wait_drdy(Delay, POLL_US)?;
+                let data = adc.read_adc1()?;
+                let t = t0.elapsed().as_secs_f64();
+                writeln!(out, "{t:.6} {} {:.9}", data.code, data.to_voltage())?;
+                n += 1;
+            }
+            adc.stop1()?;
+        }
+        Cmd::Calibrate { kind, adc2 } => {
+            let before = adc.read_calibration()?;
+            writeln!(out, "before: {before:?}")?;
+            if *adc2 {
+                adc.start2()?;
+                match kind {
+                    CalKind::SelfOffset => adc.sfocal2()?,
+                    CalKind::SysOffset => adc.syocal2()?,
+                    CalKind::SysGain => adc.sygcal2()?,
+                }
+                // ADC2 has no DRDY pin, poll the status byte for new data
+                let t0 = Instant::now();
+                while !adc.read_adc2()?.status.adc2() {
+                    if t0.elapsed() > Duration::from_secs(2) {
+                        return Err("ADC2 calibration timed out".into());
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                adc.stop2()?;
+            } else {
+                adc.start1()?;
+                match kind {
+                    CalKind::SelfOffset => adc.sfocal1()?,
+                    CalKind::SysOffset => adc.syocal1()?,
+                    CalKind::SysGain => adc.sygcal1()?,
+                }
+                adc.wait_drdy(Delay, POLL_US)?;
+                adc.stop1()?;
+            }
+            let after = adc.read_calibration()?;
+            writeln!(out, "after:  {after:?}")?;
+        }
+        Cmd::Temp => {
+            let data = read_internal(adc, InpMux::temperature())?;
+            let mc = data.to_millicelsius();
+            let sign = if mc < 0 { "-" } else { "" };
+            let mc = mc.unsigned_abs();
+            writeln!(out, "{sign}{}.{:03} °C", mc / 1000, mc % 1000)?;
+        }
+        Cmd::Supplies => {
+            // The supply monitors are attenuated by 4
+            let avdd = read_internal(adc, InpMux::analog_power())?.to_voltage() * 4.0;
+            let dvdd = read_internal(adc, InpMux::digital_power())?.to_voltage() * 4.0;
+            writeln!(out, "AVDD = {avdd:.4} V, DVDD = {dvdd:.4} V")?;
+        }
+        Cmd::Gpio { pin, state } => {
+            let mut con = adc.read_reg::<GpioCon>()?.0;
+            let mut dir = adc.read_reg::<GpioDir>()?.0;
+            let mut dat = adc.read_reg::<GpioDat>()?.0;
+            match (pin, state) {
+                (Some(pin), Some(state)) if *pin < 8 => {
+                    let bit = 1 << pin;
+                    con |= bit;
+                    match state {
+                        GpioState::Input => dir |= bit,
+                        GpioState::High => {
+                            dir &= !bit;
+                            dat |= bit;
+                        }
+                        GpioState::Low => {
+                            dir &= !bit;
+                            dat &= !bit;
+                        }
+                    }
+                    adc.write_reg(GpioDat(dat))?;
+                    adc.write_reg(GpioDir(dir))?;
+                    adc.write_reg(GpioCon(con))?;
+                    dat = adc.read_reg::<GpioDat>()?.0;
+                }
+                (Some(pin), _) if *pin >= 8 => return Err("GPIO number must be 0-7".into()),
+                _ => {}
+            }
+            for (i, name) in GPIO_PINS.iter().enumerate() {
+                if pin.is_some_and(|pin| usize::from(pin) != i) {
+                    continue;
+                }
+                let bit = 1 << i;
+                let mode = match (con & bit != 0, dir & bit != 0) {
+                    (false, _) => "not connected",
+                    (true, true) => "input",
+                    (true, false) => "output",
+                };
+                let level = if dat & bit != 0 { "high" } else { "low" };
+                writeln!(out, "GPIO{i} ({name}): {mode}, {level}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply gain and data rate options to MODE2
+fn configure<SPI, CS, DRDY, RST, SpiErr, IoErr>(
+    adc: &mut Ads1263<SPI, CS, DRDY, RST>,
+    conv: &Conversion,
+) -> Result<()>
+where
+    SPI: Transfer<u8, Error = SpiErr> + SpiWrite<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    SpiErr: Debug + 'static,
+    IoErr: Debug + 'static,
+{
+    let info = register("MODE2").ok_or("MODE2 missing")?;
+    let mut mode2 = adc.registers().get::<Mode2>();
+    if let Some(gain) = &conv.gain {
+        mode2.set_gain(parse_value(
+            info.field("GAIN").ok_or("GAIN missing")?,
+            gain,
+        )?);
+        mode2.set_bypass(false);
+    }
+    if let Some(rate) = &conv.rate {
+        mode2.set_dr(parse_value(info.field("DR").ok_or("DR missing")?, rate)?);
+    }
+    adc.write_reg(mode2)?;
+    Ok(())
+}
+
+/// Read an internal monitor at gain 1, restoring INPMUX and MODE2 afterwards
+fn read_internal<SPI, CS, DRDY, RST, SpiErr, IoErr>(
+    adc: &mut Ads1263<SPI, CS, DRDY, RST>,
+    mux: InpMux,
+) -> Result<ads126x::device::Data>
+where
+    SPI: Transfer<u8, Error = SpiErr> + SpiWrite<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    SpiErr: Debug + 'static,
+    IoErr: Debug + 'static,
+{
+    let regs = *adc.registers();
+    let mut mode2 = regs.get::<Mode2>();
+    mode2.set_gain(0);
+    mode2.set_bypass(false);
+    adc.write_reg(mode2)?;
+    adc.write_reg(mux)?;
+    adc.start1()?;
+    adc.wait_drdy(Delay, POLL_US)?;
+    let data = adc.read_adc1()?;
+    adc.stop1()?;
+    adc.write_reg(regs.get::<InpMux>())?;
+    adc.write_reg(regs.get::<Mode2>())?;
+    Ok(data)
+}
+
+/// Simulated ADS1263 with AINn at (n + 1) * 100 mV
+fn simulated() -> SimAds126x {
+    let sim = SimAds126x::ads1263();
+    let inputs = [
+        InpMuxMuxx::Ain0,
+        InpMuxMuxx::Ain1,
+        InpMuxMuxx::Ain2,
+        InpMuxMuxx::Ain3,
+        InpMuxMuxx::Ain4,
+        InpMuxMuxx::Ain5,
+        InpMuxMuxx::Ain6,
+        InpMuxMuxx::Ain7,
+        InpMuxMuxx::Ain8,
+        InpMuxMuxx::Ain9,
+    ];
+    for (i, input) in inputs.into_iter().enumerate() {
+        sim.set_input(input, (i as i32 + 1) * 100_000);
+    }
+    sim
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut out = io::stdout().lock();
+
+    if args.sim {
+        let sim = simulated();
+        let mut adc = Ads1263::new(sim.spi(), sim.cs(), sim.drdy(), sim.rst());
+        return run(&mut adc, &args.cmd, &mut out);
+    }
+
+    // Initialize GPIOs
+    let mut gpio = Chip::new(&args.gpio)?;
+    let cs = gpio
+        .get_line(args.cs)?
+        .request(LineRequestFlags::OUTPUT, 1, "ads126x")?;
+    let drdy = gpio
+        .get_line(args.drdy)?
+        .request(LineRequestFlags::INPUT, 1, "ads126x")?;
+    let rst = gpio
+        .get_line(args.rst)?
+        .request(LineRequestFlags::OUTPUT, 1, "ads126x")?;
+
+    // Initialize SPI
+    let mut spi = Spidev::open(&args.spi)?;
+    let mut spi_options = SpidevOptions::default();
+    spi_options.mode(SpiModeFlags::SPI_MODE_1);
+    spi_options.max_speed_hz(args.speed);
+    spi.configure(&spi_options)?;
+
+    let mut adc = Ads1263::new(
+        spi,
+        CdevPin::new(cs)?,
+        CdevPin::new(drdy)?,
+        CdevPin::new(rst)?,
+    );
+    run(&mut adc, &args.cmd, &mut out)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{run, simulated, Args};
+    use ads126x::{sim::SimAds126x, Ads1263};
+
+    /// Run a command line against a fresh simulated device
+    fn cli(args: &[&str]) -> String {
+        cli_with(simulated(), args)
+    }
+
+    /// Run a command line against `sim`
+    fn cli_with(sim: SimAds126x, args: &[&str]) -> String {
+        let args = Args::parse_from(["ads126x", "--sim"].iter().chain(args));
+        let mut adc = Ads1263::new(sim.spi(), sim.cs(), sim.drdy(), sim.rst());
+        let mut out = Vec::new();
+        run(&mut adc, &args.cmd, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(cli(&["id"]), "REV_ID = 1, DEV_ID = ADS1263\n");
+        assert!(cli(&["dump"]).contains("MODE2     (05h) = 04h: DR = 20 SPS, GAIN = 1"));
+        assert_eq!(cli(&["get", "mode2.dr"]), "MODE2.DR = 20 SPS (4)\n");
+        assert_eq!(
+            cli(&["set", "mode2.dr", "400"]),
+            "MODE2 = 08h: DR = 400 SPS, GAIN = 1, BYPASS = PGA enabled\n"
+        );
+        assert_eq!(
+            cli(&["read", "AIN1:AIN0", "--gain", "8"]),
+            "AIN1:AIN0: 687194767 (0.1000000 V)\n"
+        );
+        assert_eq!(
+            cli(&["scan", "AIN0", "AIN4"]),
+            "AIN0: 0.1000000 V\nAIN4: 0.5000000 V\n"
+        );
+        assert_eq!(cli(&["stream", "AIN2", "-n", "3"]).lines().count(), 3);
+        assert!(cli(&["calibrate"]).contains("after:  Calibration"));
+        assert_eq!(cli(&["temp"]), "25.000 °C\n");
+        assert_eq!(cli(&["supplies"]), "AVDD = 5.0000 V, DVDD = 3.3000 V\n");
+        assert_eq!(cli(&["gpio", "2", "high"]), "GPIO2 (AIN5): output, high\n");
+    }
+
+    #[test]
+    fn test_temp_below_zero() {
+        for (mc, out) in [(-500, "-0.500 °C\n"), (-12_250, "-12.250 °C\n")] {
+            let sim = simulated();
+            sim.set_temperature(mc);
+            assert_eq!(cli_with(sim, &["temp"]), out);
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Recording samples as CSV, JSON Lines or a compact binary capture
+//!
+//! All sinks implement [SampleSink] and write to a [ByteWrite], which is implemented for closures so
+//! that any output can be used, e.g. `|b: &[u8]| file.write_all(b)` on std.
+//!
+//! The binary capture starts with a header holding the device, the register snapshot (including
+//! calibration) and the record length, followed by fixed-size records. [CaptureReader] turns a capture
+//! back into [Sample]s.
+//!
+//! | Offset | Size | Header field                                   |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 4    | Magic `ADSC`                                   |
+//! | 4      | 1    | Format version (1)                             |
+//! | 5      | 1    | Device ([IdDev])                               |
+//! | 6      | 1    | Record length in bytes                         |
+//! | 7      | 1    | Reserved                                       |
+//! | 8      | 27   | Registers 00h..1Ah ([RegisterMap])             |
+//!
+//! | Offset | Size | Record field (little endian)                   |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 8    | Timestamp in us                                |
+//! | 8      | 1    | Channel                                        |
+//! | 9      | 1    | [Status] byte                                  |
+//! | 10     | 1    | PGA gain                                       |
+//! | 11     | 1    | Reserved                                       |
+//! | 12     | 4    | Conversion code                                |
+//! | 16     | 4    | Reference voltage in uV                        |
+
+use core::{convert::Infallible, fmt};
+
+use crate::{
+    device::{Data, Status},
+    metadata::STATUS_FIELDS,
+    registers::{IdDev, RegisterMap, REGISTER_COUNT},
+};
+
+/// Magic at the start of a binary capture
+pub const MAGIC: [u8; 4] = *b"ADSC";
+/// Binary capture format version
+pub const VERSION: u8 = 1;
+/// Length of the binary capture header
+pub const HEADER_LEN: usize = 8 + REGISTER_COUNT;
+/// Length of a record in a binary capture
+pub const RECORD_LEN: usize = 20;
+
+/// Byte output of a [SampleSink]
+pub trait ByteWrite {
+    type Error;
+
+    /// Write all of `buf`
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<F, E> ByteWrite for F
+where
+    F: FnMut(&[u8]) -> Result<(), E>,
+{
+    type Error = E;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), E> {
+        self(buf)
+    }
+}
+
+/// A conversion result tagged with channel and time
+#[derive(Debug, Copy, Clone)]
+pub struct Sample {
+    /// Channel, e.g. the index in a scan list or the [InpMux](crate::registers::InpMux) byte
+    pub channel: u8,
+    /// Time of the conversion in us since an arbitrary epoch
+    pub timestamp_us: u64,
+    /// Conversion result
+    pub data: Data,
+}
+
+impl Sample {
+    pub fn new(channel: u8, timestamp_us: u64, data: Data) -> Self {
+        Self {
+            channel,
+            timestamp_us,
+            data,
+        }
+    }
+
+    /// Encode as a binary capture record
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        let mut buf = [0; RECORD_LEN];
+        buf[0..8].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        buf[8] = self.channel;
+        buf[9] = self.data.status.0;
+        buf[10] = self.data.gain;
+        buf[12..16].copy_from_slice(&self.data.code.to_le_bytes());
+        buf[16..20].copy_from_slice(&self.data.vref_uv.to_le_bytes());
+        buf
+    }
+
+    /// Decode a binary capture record
+    pub fn from_bytes(buf: &[u8; RECORD_LEN]) -> Self {
+        let word = |i: usize| [buf[i], buf[i + 1], buf[i + 2], buf[i + 3]];
+        let mut t = [0; 8];
+        t.copy_from_slice(&buf[0..8]);
+        Self {
+            channel: buf[8],
+            timestamp_us: u64::from_le_bytes(t),
+            data: Data::new(
+                Status(buf[9]),
+                i32::from_le_bytes(word(12)),
+                u32::from_le_bytes(word(16)),
+                buf[10],
+            ),
+        }
+    }
+}
+
+/// Destination for samples
+pub trait SampleSink {
+    type Error;
+
+    /// Write one sample
+    fn write(&mut self, sample: &Sample) -> Result<(), Self::Error>;
+}
+
+/// Fixed-size line buffer, lines written by the text sinks are well below its size
+struct Line {
+    buf: [u8; 256],
+    len: usize,
+}
+
+impl Line {
+    fn new() -> Self {
+        Self {
+            buf: [0; 256],
+            len: 0,
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl fmt::Write for Line {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Voltage in V with nV resolution, formatted without floating point
+struct Volts(i64);
+
+impl fmt::Display for Volts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let nv = self.0.unsigned_abs();
+        write!(f, "{sign}{}.{:09}", nv / 1_000_000_000, nv % 1_000_000_000)
+    }
+}
+
+/// Names of the status flags that are set, separated by `sep` and optionally quoted
+struct Flags {
+    status: Status,
+    sep: &'static str,
+    quote: &'static str,
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for field in STATUS_FIELDS
+            .iter()
+            .filter(|field| field.get(self.status.0) != 0)
+        {
+            if !first {
+                f.write_str(self.sep)?;
+            }
+            write!(f, "{}{}{}", self.quote, field.name, self.quote)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Writes samples as CSV with a header line
+///
+/// Columns are `timestamp_us,channel,code,volts,status,flags` where `status` is the raw status byte
+/// and `flags` lists the set status bits separated by `|`, e.g. `ADC1|PGAH_ALM`.
+pub struct CsvSink<W> {
+    w: W,
+    header: bool,
+}
+
+impl<W: ByteWrite> CsvSink<W> {
+    pub fn new(w: W) -> Self {
+        Self { w, header: false }
+    }
+
+    /// Release the output
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+impl<W: ByteWrite> SampleSink for CsvSink<W> {
+    type Error = W::Error;
+
+    fn write(&mut self, sample: &Sample) -> Result<(), W::Error> {
+        use fmt::Write;
+
+        if !self.header {
+            self.w
+                .write_all(b"timestamp_us,channel,code,volts,status,flags\n")?;
+            self.header = true;
+        }
+        let mut line = Line::new();
+        let _ = writeln!(
+            line,
+            "{},{},{},{},{},{}",
+            sample.timestamp_us,
+            sample.channel,
+            sample.data.code,
+            Volts(sample.data.to_nanovolts()),
+            sample.data.status.0,
+            Flags {
+                status: sample.data.status,
+                sep: "|",
+                quote: ""
+            }
+        );
+        self.w.write_all(line.as_bytes())
+    }
+}
+
+/// Writes samples as JSON Lines, one object per sample
+///
+/// For example `{"timestamp_us":1000,"channel":0,"code":1234,"volts":0.000001437,"status":64,"flags":["ADC1"]}`.
+pub struct JsonLinesSink<W> {
+    w: W,
+}
+
+impl<W: ByteWrite> JsonLinesSink<W> {
+    pub fn new(w: W) -> Self {
+        Self { w }
+    }
+
+    /// Release the output
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+impl<W: ByteWrite> SampleSink for JsonLinesSink<W> {
+    type Error = W::Error;
+
+    fn write(&mut self, sample: &Sample) -> Result<(), W::Error> {
+        use fmt::Write;
+
+        let mut line = Line::new();
+        let _ = writeln!(
+            line,
+            r#"{{"timestamp_us":{},"channel":{},"code":{},"volts":{},"status":{},"flags":[{}]}}"#,
+            sample.timestamp_us,
+            sample.channel,
+            sample.data.code,
+            Volts(sample.data.to_nanovolts()),
+            sample.data.status.0,
+            Flags {
+                status: sample.data.status,
+                sep: ",",
+                quote: "\""
+            }
+        );
+        self.w.write_all(line.as_bytes())
+    }
+}
+
+/// Header of a binary capture
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CaptureHeader {
+    /// Device the capture was taken from
+    pub device: IdDev,
+    /// Registers at the start of the capture, including calibration, e.g. the driver's
+    /// [registers](crate::device::Ads126x::registers) after [sync_registers](crate::device::Ads126x::sync_registers)
+    pub registers: RegisterMap,
+}
+
+impl CaptureHeader {
+    pub fn new(device: IdDev, registers: RegisterMap) -> Self {
+        Self { device, registers }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut buf = [0; HEADER_LEN];
+        buf[0..4].copy_from_slice(&MAGIC);
+        buf[4] = VERSION;
+        buf[5] = self.device as u8;
+        buf[6] = RECORD_LEN as u8;
+        buf[8..].copy_from_slice(&self.registers.0);
+        buf
+    }
+
+    /// Decode header, returns the header and the record length
+    pub fn from_bytes(buf: &[u8]) -> Result<(Self, usize), CaptureError> {
+        if buf.len() < HEADER_LEN {
+            return Err(CaptureError::Truncated);
+        }
+        if buf[0..4] != MAGIC {
+            return Err(CaptureError::BadMagic);
+        }
+        if buf[4] != VERSION {
+            return Err(CaptureError::UnsupportedVersion(buf[4]));
+        }
+        let device = match buf[5] {
+            0 => IdDev::Ads1262,
+            1 => IdDev::Ads1263,
+            dev => return Err(CaptureError::UnknownDevice(dev)),
+        };
+        let record_len = usize::from(buf[6]);
+        if record_len < RECORD_LEN {
+            return Err(CaptureError::Truncated);
+        }
+        let mut registers = RegisterMap::default();
+        registers.0.copy_from_slice(&buf[8..HEADER_LEN]);
+        Ok((Self { device, registers }, record_len))
+    }
+}
+
+/// Writes samples as a binary capture, see the [module](self) documentation for the format
+pub struct BinarySink<W> {
+    w: W,
+    header: Option<CaptureHeader>,
+}
+
+impl<W: ByteWrite> BinarySink<W> {
+    /// Create a capture, the header is written together with the first sample or by [Self::finish]
+    pub fn new(w: W, header: CaptureHeader) -> Self {
+        Self {
+            w,
+            header: Some(header),
+        }
+    }
+
+    fn write_header(&mut self) -> Result<(), W::Error> {
+        if let Some(header) = self.header.take() {
+            self.w.write_all(&header.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Make sure the header is written and release the output
+    pub fn finish(mut self) -> Result<W, W::Error> {
+        self.write_header()?;
+        Ok(self.w)
+    }
+}
+
+impl<W: ByteWrite> SampleSink for BinarySink<W> {
+    type Error = W::Error;
+
+    fn write(&mut self, sample: &Sample) -> Result<(), W::Error> {
+        self.write_header()?;
+        self.w.write_all(&sample.to_bytes())
+    }
+}
+
+/// Error reading a binary capture
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureError {
+    /// Capture does not start with [MAGIC]
+    BadMagic,
+    /// Capture was written by a newer format version
+    UnsupportedVersion(u8),
+    /// Header holds an unknown device
+    UnknownDevice(u8),
+    /// Capture ends within the header or a record
+    Truncated,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::BadMagic => write!(f, "not an ADS126x capture"),
+            CaptureError::UnsupportedVersion(v) => write!(f, "unsupported capture version {v}"),
+            CaptureError::UnknownDevice(d) => write!(f, "unknown device {d}"),
+            CaptureError::Truncated => write!(f, "capture is truncated"),
+        }
+    }
+}
+
+impl core::error::Error for CaptureError {}
+
+impl From<Infallible> for CaptureError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+/// Reads the samples of a binary capture held in memory
+pub struct CaptureReader<'a> {
+    header: CaptureHeader,
+    record_len: usize,
+    records: &'a [u8],
+}
+
+impl<'a> CaptureReader<'a> {
+    pub fn new(capture: &'a [u8]) -> Result<Self, CaptureError> {
+        let (header, record_len) = CaptureHeader::from_bytes(capture)?;
+        Ok(Self {
+            header,
+            record_len,
+            records: &capture[HEADER_LEN..],
+        })
+    }
+
+    /// Capture header
+    pub fn header(&self) -> &CaptureHeader {
+        &self.header
+    }
+
+    /// Number of complete records remaining
+    pub fn remaining(&self) -> usize {
+        self.records.len() / self.record_len
+    }
+}
+
+impl Iterator for CaptureReader<'_> {
+    type Item = Result<Sample, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.records.is_empty() {
+            return None;
+        }
+        if self.records.len() < self.record_len {
+            self.records = &[];
+            return Some(Err(CaptureError::Truncated));
+        }
+        let (record, rest) = self.records.split_at(self.record_len);
+        self.records = rest;
+        let mut buf = [0; RECORD_LEN];
+        buf.copy_from_slice(&record[..RECORD_LEN]);
+        Some(Ok(Sample::from_bytes(&buf)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::convert::Infallible;
+    use std::{string::String, vec::Vec};
+
+    use super::*;
+    use crate::registers::{Mode2, Mode2Dr, Mode2Gain};
+
+    fn samples() -> [Sample; 2] {
+        [
+            Sample::new(0, 1_000, Data::new(Status(0x40), 1 << 30, 2_500_000, 1)),
+            Sample::new(3, 2_500, Data::new(Status(0x44), -(1 << 28), 2_500_000, 8)),
+        ]
+    }
+
+    #[test]
+    fn test_text_sinks() {
+        let mut csv = String::new();
+        let mut sink = CsvSink::new(|b: &[u8]| {
+            csv.push_str(core::str::from_utf8(b).unwrap());
+            Ok::<(), Infallible>(())
+        });
+        samples().iter().try_for_each(|s| sink.write(s)).unwrap();
+        assert_eq!(
+            csv,
+            "timestamp_us,channel,code,volts,status,flags\n\
+             1000,0,1073741824,1.250000000,64,ADC1\n\
+             2500,3,-268435456,-0.039062500,68,PGAH_ALM|ADC1\n"
+        );
+
+        let mut json = String::new();
+        let mut sink = JsonLinesSink::new(|b: &[u8]| {
+            json.push_str(core::str::from_utf8(b).unwrap());
+            Ok::<(), Infallible>(())
+        });
+        sink.write(&samples()[1]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["code"], -(1 << 28));
+        assert_eq!(value["flags"], serde_json::json!(["PGAH_ALM", "ADC1"]));
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let mut regs = RegisterMap::default();
+        regs.set(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false));
+        let header = CaptureHeader::new(IdDev::Ads1263, regs);
+
+        let mut bytes = Vec::new();
+        let mut sink = BinarySink::new(
+            |b: &[u8]| {
+                bytes.extend_from_slice(b);
+                Ok::<(), Infallible>(())
+            },
+            header,
+        );
+        samples().iter().try_for_each(|s| sink.write(s)).unwrap();
+        let _ = sink.finish().unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 2 * RECORD_LEN);
+
+        let reader = CaptureReader::new(&bytes).unwrap();
+        assert_eq!(*reader.header(), header);
+        let back: Vec<_> = reader.map(Result::unwrap).collect();
+        for (a, b) in back.iter().zip(samples().iter()) {
+            assert_eq!((a.channel, a.timestamp_us), (b.channel, b.timestamp_us));
+            assert_eq!(a.data.to_nanovolts(), b.data.to_nanovolts());
+            assert_eq!(a.data.status.0, b.data.status.0);
+        }
+
+        let truncated = &bytes[..bytes.len() - 1];
+        let mut reader = CaptureReader::new(truncated).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(reader.next().unwrap().unwrap_err(), CaptureError::Truncated);
+        assert_eq!(
+            CaptureReader::new(&bytes[1..]).err(),
+            Some(CaptureError::BadMagic)
+        );
+    }
+}
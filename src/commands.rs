@@ -1,4 +1,5 @@
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[allow(dead_code)]
 pub enum Command {
     /// ## NOP Command
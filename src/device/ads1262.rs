@@ -1,5 +1,3 @@
-use core::marker::PhantomData;
-
 use embedded_hal::{
     blocking::spi::{Transfer, Write},
     digital::v2::OutputPin,
@@ -13,17 +11,9 @@ impl<SPI, CS, DRDY, RST, SpiErr, IoErr> Ads126x<SPI, CS, DRDY, RST, _Ads1262>
 where
     SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
     CS: OutputPin<Error = IoErr>,
-    RST: OutputPin<Error = IoErr>,
 {
     pub fn new(spi: SPI, cs: CS, drdy: DRDY, rst: RST) -> Self {
-        Self {
-            _x: PhantomData,
-            spi,
-            cs,
-            drdy,
-            rst,
-            interface: Default::default(),
-        }
+        Self::from_parts(spi, cs, drdy, rst)
     }
 
     //No additional functions
@@ -1,7 +1,8 @@
-use core::marker::PhantomData;
-
 use embedded_hal::{
-    blocking::spi::{Transfer, Write},
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
     digital::v2::OutputPin,
 };
 
@@ -18,19 +19,18 @@ impl<SPI, CS, DRDY, RST, SpiErr, IoErr> Ads126x<SPI, CS, DRDY, RST, _Ads1263>
 where
     SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
     CS: OutputPin<Error = IoErr>,
-    RST: OutputPin<Error = IoErr>,
 {
     pub fn new(spi: SPI, cs: CS, drdy: DRDY, rst: RST) -> Self {
-        Self {
-            _x: PhantomData,
-            spi,
-            cs,
-            drdy,
-            rst,
-            interface: Default::default(),
-        }
+        Self::from_parts(spi, cs, drdy, rst)
     }
+}
 
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr>
+    Ads126x<SPI, CS, DRDY, RST, _Ads1263, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+{
     /// Send START2 command to start ADC2 conversion
     pub fn start2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
         self.send_command(Command::Start2)
@@ -43,32 +43,57 @@ where
 
     /// Read data from ADC2
     pub fn read_adc2(&mut self) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
-        self.read_data(Some(Command::RData1))
+        self.read_data(Some(Command::RData2))
     }
 
-    pub fn read_offs_cal2(&mut self) -> Result<i16, Ads126xError<SpiErr, IoErr>> {
-        let mut data = [Command::RReg.reg(ADC2OFC0), 0x01, 0x00, 0x00];
+    /// Read ADC2 every `us` microseconds until [Status::adc2](super::Status::adc2) reports new data
+    pub(crate) fn next_adc2<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        loop {
+            let data = self.read_adc2()?;
+            if data.status.adc2() {
+                return Ok(data);
+            }
+            delay.delay_us(us);
+        }
+    }
 
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi
-            .transfer(&mut data)
-            .map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+    /// Send SFOCAL2 command to start ADC2 self offset calibration
+    ///
+    /// ADC2 must be converting. The result is stored in ADC2OFC, see [Self::read_calibration].
+    pub fn sfocal2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Sfocal2)
+    }
 
-        let value = (data[2] as i16) | (data[3] as i16) << 8;
+    /// Send SYOCAL2 command to start ADC2 system offset calibration, the input must be shorted externally
+    pub fn syocal2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Syocal2)
+    }
+
+    /// Send SYGCAL2 command to start ADC2 system gain calibration, a full-scale voltage must be applied
+    pub fn sygcal2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Sygcal2)
+    }
+
+    pub fn read_offs_cal2(&mut self) -> Result<i16, Ads126xError<SpiErr, IoErr>> {
+        let mut data = [0; 2];
+        self.read_regs(ADC2OFC0, &mut data)?;
+
+        let value = i16::from_le_bytes(data);
         Ok(value)
     }
 
     pub fn read_fs_cal2(&mut self) -> Result<u16, Ads126xError<SpiErr, IoErr>> {
-        let mut data = [Command::RReg.reg(ADC2FSC0), 0x01, 0x00, 0x00];
-
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi
-            .transfer(&mut data)
-            .map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+        let mut data = [0; 2];
+        self.read_regs(ADC2FSC0, &mut data)?;
 
-        let value = (data[2] as u16) | (data[3] as u16) << 8;
+        let value = u16::from_le_bytes(data);
         Ok(value)
     }
 }
@@ -0,0 +1,378 @@
+use embedded_hal::{
+    blocking::{
+        delay::{DelayMs, DelayUs},
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    registers::{Adc2CfgGain, IdDev, Mode2Gain, Register},
+    Ads1262, Ads1263,
+};
+
+use super::{
+    Ads126xError, AutoRange, AutoZero, CalInputs, Data, GainCal, GainTable, Monitor, Monitored,
+    NoiseStats, NoiseTest, RecalEvent, Recalibration, Reference, SelfTestLimits, SelfTestReport,
+};
+
+/// Either a ADS1262 or ADS1263 device, detected at runtime using [AnyAds126x::probe].
+///
+/// ADC2 functions return [Ads126xError::Unsupported] on a ADS1262.
+pub enum AnyAds126x<SPI, CS, DRDY, RST> {
+    Ads1262(Ads1262<SPI, CS, DRDY, RST>),
+    Ads1263(Ads1263<SPI, CS, DRDY, RST>),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $dev:ident => $e:expr) => {
+        match $self {
+            AnyAds126x::Ads1262($dev) => $e,
+            AnyAds126x::Ads1263($dev) => $e,
+        }
+    };
+}
+
+macro_rules! adc2 {
+    ($self:ident, $dev:ident => $e:expr) => {
+        match $self {
+            AnyAds126x::Ads1262(_) => Err(Ads126xError::Unsupported),
+            AnyAds126x::Ads1263($dev) => $e,
+        }
+    };
+}
+
+impl<SPI, CS, DRDY, RST, SpiErr, IoErr> AnyAds126x<SPI, CS, DRDY, RST>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+{
+    /// Read the ID register and create a driver for the detected device
+    pub fn probe(
+        spi: SPI,
+        cs: CS,
+        drdy: DRDY,
+        rst: RST,
+    ) -> Result<Self, Ads126xError<SpiErr, IoErr>> {
+        let mut dev = Ads1262::from_parts(spi, cs, drdy, rst);
+        match dev.probe_id()?.1 {
+            IdDev::Ads1262 => Ok(Self::Ads1262(dev)),
+            IdDev::Ads1263 => Ok(Self::Ads1263(dev.into_device())),
+        }
+    }
+
+    /// Detected device
+    pub fn device(&self) -> IdDev {
+        match self {
+            AnyAds126x::Ads1262(_) => IdDev::Ads1262,
+            AnyAds126x::Ads1263(_) => IdDev::Ads1263,
+        }
+    }
+
+    /// See [Ads126x::read_reg](super::Ads126x::read_reg)
+    pub fn read_reg<REG>(&mut self) -> Result<REG, Ads126xError<SpiErr, IoErr>>
+    where
+        REG: Register,
+    {
+        dispatch!(self, dev => dev.read_reg())
+    }
+
+    /// See [Ads126x::write_reg](super::Ads126x::write_reg)
+    pub fn write_reg<REG>(&mut self, reg: REG) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        REG: Register,
+    {
+        dispatch!(self, dev => dev.write_reg(reg))
+    }
+
+    /// See [Ads126x::soft_reset](super::Ads126x::soft_reset)
+    pub fn soft_reset<DELAY>(&mut self, delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.soft_reset(delay))
+    }
+
+    /// See [Ads126x::clear_reset_flag](super::Ads126x::clear_reset_flag)
+    pub fn clear_reset_flag(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.clear_reset_flag())
+    }
+
+    /// See [Ads126x::restore_configuration](super::Ads126x::restore_configuration)
+    pub fn restore_configuration(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.restore_configuration())
+    }
+
+    /// See [Ads126x::check_registers](super::Ads126x::check_registers)
+    pub fn check_registers(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.check_registers())
+    }
+
+    /// See [Ads126x::reset_count](super::Ads126x::reset_count)
+    pub fn reset_count(&self) -> u32 {
+        dispatch!(self, dev => dev.reset_count())
+    }
+
+    /// See [Ads126x::set_reference](super::Ads126x::set_reference)
+    pub fn set_reference(
+        &mut self,
+        reference: Reference,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.set_reference(reference))
+    }
+
+    /// See [Ads126x::start1](super::Ads126x::start1)
+    pub fn start1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.start1())
+    }
+
+    /// See [Ads126x::stop1](super::Ads126x::stop1)
+    pub fn stop1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.stop1())
+    }
+
+    /// See [Ads126x::read_adc1](super::Ads126x::read_adc1)
+    pub fn read_adc1(&mut self) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.read_adc1())
+    }
+
+    /// See [Ads126x::read_direct](super::Ads126x::read_direct)
+    pub fn read_direct(&mut self) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.read_direct())
+    }
+
+    /// See [Ads126x::read_offs_cal1](super::Ads126x::read_offs_cal1)
+    pub fn read_offs_cal1(&mut self) -> Result<i32, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.read_offs_cal1())
+    }
+
+    /// See [Ads126x::read_fs_cal1](super::Ads126x::read_fs_cal1)
+    pub fn read_fs_cal1(&mut self) -> Result<u32, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.read_fs_cal1())
+    }
+
+    /// See [Ads126x::set_gain1](super::Ads126x::set_gain1)
+    pub fn set_gain1(
+        &mut self,
+        gain: Mode2Gain,
+        table: &GainTable,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.set_gain1(gain, table))
+    }
+
+    /// See [Ads126x::read_reg_at](super::Ads126x::read_reg_at)
+    pub fn read_reg_at(&mut self, addr: u8) -> Result<u8, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.read_reg_at(addr))
+    }
+
+    /// See [Ads126x::write_reg_at](super::Ads126x::write_reg_at)
+    pub fn write_reg_at(&mut self, addr: u8, value: u8) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.write_reg_at(addr, value))
+    }
+
+    /// See [Ads126x::sfocal1](super::Ads126x::sfocal1)
+    pub fn sfocal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.sfocal1())
+    }
+
+    /// See [Ads126x::syocal1](super::Ads126x::syocal1)
+    pub fn syocal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.syocal1())
+    }
+
+    /// See [Ads126x::sygcal1](super::Ads126x::sygcal1)
+    pub fn sygcal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.sygcal1())
+    }
+
+    /// Send START2 command to start ADC2 conversion
+    pub fn start2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.start2())
+    }
+
+    /// Send STOP2 command to stop ADC2 conversion
+    pub fn stop2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.stop2())
+    }
+
+    /// Read data from ADC2
+    pub fn read_adc2(&mut self) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.read_adc2())
+    }
+
+    /// Send SFOCAL2 command to start ADC2 self offset calibration
+    pub fn sfocal2(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.sfocal2())
+    }
+
+    pub fn read_offs_cal2(&mut self) -> Result<i16, Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.read_offs_cal2())
+    }
+
+    pub fn read_fs_cal2(&mut self) -> Result<u16, Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.read_fs_cal2())
+    }
+
+    /// Set the ADC2 PGA gain and write its correction from `table`
+    pub fn set_gain2(
+        &mut self,
+        gain: Adc2CfgGain,
+        table: &GainTable,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.set_gain2(gain, table))
+    }
+
+    /// Read ADC2 with polarity-swap auto-zero
+    pub fn read_adc2_autozero<DELAY>(
+        &mut self,
+        az: &mut AutoZero,
+        delay: DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        adc2!(self, dev => dev.read_adc2_autozero(az, delay, us))
+    }
+}
+
+impl<SPI, CS, DRDY, RST, SpiErr, IoErr> AnyAds126x<SPI, CS, DRDY, RST>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    RST: OutputPin<Error = IoErr>,
+{
+    /// See [Ads126x::reset](super::Ads126x::reset)
+    pub fn reset<DELAY>(&mut self, delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayMs<u32>,
+    {
+        dispatch!(self, dev => dev.reset(delay))
+    }
+}
+
+impl<SPI, CS, DRDY, RST, SpiErr, IoErr> AnyAds126x<SPI, CS, DRDY, RST>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+{
+    /// See [Ads126x::drdy](super::Ads126x::drdy)
+    pub fn drdy(&mut self) -> Result<bool, Ads126xError<SpiErr, IoErr>> {
+        dispatch!(self, dev => dev.drdy())
+    }
+
+    /// See [Ads126x::wait_drdy](super::Ads126x::wait_drdy)
+    pub fn wait_drdy<DELAY>(
+        &mut self,
+        delay: DELAY,
+        us: u32,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.wait_drdy(delay, us))
+    }
+
+    /// See [Ads126x::characterize](super::Ads126x::characterize)
+    pub fn characterize<DELAY>(
+        &mut self,
+        delay: DELAY,
+        us: u32,
+        test: NoiseTest,
+        codes: &mut [i32],
+        histogram: &mut [u32],
+    ) -> Result<NoiseStats, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.characterize(delay, us, test, codes, histogram))
+    }
+
+    /// See [Ads126x::read_autoranged](super::Ads126x::read_autoranged)
+    pub fn read_autoranged<DELAY>(
+        &mut self,
+        range: &mut AutoRange,
+        delay: DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.read_autoranged(range, delay, us))
+    }
+
+    /// See [Ads126x::calibrate_gain1](super::Ads126x::calibrate_gain1)
+    pub fn calibrate_gain1<DELAY>(
+        &mut self,
+        delay: DELAY,
+        us: u32,
+        gain: Mode2Gain,
+        inputs: &CalInputs,
+    ) -> Result<GainCal, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.calibrate_gain1(delay, us, gain, inputs))
+    }
+
+    /// See [Ads126x::self_test](super::Ads126x::self_test)
+    pub fn self_test<DELAY>(
+        &mut self,
+        delay: DELAY,
+        us: u32,
+        limits: &SelfTestLimits,
+    ) -> Result<SelfTestReport, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.self_test(delay, us, limits))
+    }
+
+    /// See [Ads126x::recalibrate_if_due](super::Ads126x::recalibrate_if_due)
+    pub fn recalibrate_if_due<DELAY>(
+        &mut self,
+        schedule: &mut Recalibration,
+        delay: DELAY,
+        us: u32,
+        now_ms: u32,
+    ) -> Result<Option<RecalEvent>, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        dispatch!(self, dev => dev.recalibrate_if_due(schedule, delay, us, now_ms))
+    }
+
+    /// Select the first channel of `monitor` on ADC2 and start both ADCs
+    pub fn start_monitor<const N: usize>(
+        &mut self,
+        monitor: &mut Monitor<N>,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        adc2!(self, dev => dev.start_monitor(monitor))
+    }
+
+    /// Read the next ADC1 conversion annotated with the latest ADC2 health readings
+    pub fn read_monitored<DELAY, const N: usize>(
+        &mut self,
+        monitor: &mut Monitor<N>,
+        delay: DELAY,
+        us: u32,
+    ) -> Result<Monitored<N>, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        adc2!(self, dev => dev.read_monitored(monitor, delay, us))
+    }
+}
+
+impl<SPI, CS, DRDY, RST> From<Ads1262<SPI, CS, DRDY, RST>> for AnyAds126x<SPI, CS, DRDY, RST> {
+    fn from(dev: Ads1262<SPI, CS, DRDY, RST>) -> Self {
+        Self::Ads1262(dev)
+    }
+}
+
+impl<SPI, CS, DRDY, RST> From<Ads1263<SPI, CS, DRDY, RST>> for AnyAds126x<SPI, CS, DRDY, RST> {
+    fn from(dev: Ads1263<SPI, CS, DRDY, RST>) -> Self {
+        Self::Ads1263(dev)
+    }
+}
@@ -0,0 +1,218 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::registers::{Mode2, Mode2Gain};
+
+use super::{private, Ads126x, Ads126xError, Data, GainTable};
+
+/// Gain change decided by [AutoRange::update]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GainStep {
+    Up,
+    Down,
+}
+
+/// Automatic PGA gain ranging for ADC1, see [Ads126x::read_autoranged]
+///
+/// Starts at the lowest allowed gain. The gain is stepped down as soon as a reading raises a PGA
+/// alarm ([Status::pgah_alm](super::Status::pgah_alm), [Status::pgal_alm](super::Status::pgal_alm))
+/// or exceeds `down_percent` of full scale, and stepped up after `hold` consecutive readings below
+/// `up_percent`. Keep `up_percent` below half of `down_percent` so that a step up does not
+/// immediately cause a step down.
+#[derive(Debug, Clone)]
+pub struct AutoRange {
+    /// [Mode2::gain] setting in use
+    gain: u8,
+    min: u8,
+    max: u8,
+    /// Step up below this percentage of full scale
+    pub up_percent: u8,
+    /// Step down above this percentage of full scale
+    pub down_percent: u8,
+    /// Consecutive readings below `up_percent` before stepping up
+    pub hold: u8,
+    /// Conversions discarded after each gain change
+    pub settle: u8,
+    below: u8,
+    table: Option<GainTable>,
+}
+
+impl AutoRange {
+    /// Range between `min` and `max` gain, stepping up below 40 % and down above 90 % of full scale
+    pub fn new(min: Mode2Gain, max: Mode2Gain) -> Self {
+        let (min, max) = (min as u8, max as u8);
+        Self {
+            gain: min,
+            min,
+            max: max.max(min),
+            up_percent: 40,
+            down_percent: 90,
+            hold: 2,
+            settle: 1,
+            below: 0,
+            table: None,
+        }
+    }
+
+    /// Write the correction of each gain from `table` into OFCAL/FSCAL when changing gain
+    pub fn with_table(self, table: GainTable) -> Self {
+        Self {
+            table: Some(table),
+            ..self
+        }
+    }
+
+    /// PGA gain in use
+    pub fn gain(&self) -> u8 {
+        1 << self.gain
+    }
+
+    /// Decide on a gain change after `data` was read, the new gain is in effect when `Some` is returned
+    pub fn update(&mut self, data: &Data) -> Option<GainStep> {
+        let percent = (u64::from(data.code.unsigned_abs()) * 100) >> 31;
+        let over = data.status.pgah_alm()
+            || data.status.pgal_alm()
+            || percent > u64::from(self.down_percent);
+        if over {
+            self.below = 0;
+            if self.gain > self.min {
+                self.gain -= 1;
+                return Some(GainStep::Down);
+            }
+        } else if percent < u64::from(self.up_percent) && self.gain < self.max {
+            self.below += 1;
+            if self.below >= self.hold {
+                self.below = 0;
+                self.gain += 1;
+                return Some(GainStep::Up);
+            }
+        } else {
+            self.below = 0;
+        }
+        None
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Read ADC1 with automatic gain ranging, polling DRDY every `us` microseconds
+    ///
+    /// ADC1 must be converting continuously with the PGA enabled and the status byte enabled in
+    /// [Interface](crate::registers::Interface) for PGA alarms to be seen. A reading that is out of
+    /// range is discarded and repeated at the lower gain, the returned [Data::gain] is the gain used
+    /// for the returned reading.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn read_autoranged<DELAY>(
+        &mut self,
+        range: &mut AutoRange,
+        mut delay: DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        if self.state.regs.get::<Mode2>().gain() != range.gain {
+            self.apply_gain(range, &mut delay, us)?;
+        }
+        loop {
+            while !self.drdy()? {
+                delay.delay_us(us);
+            }
+            let data = self.read_adc1()?;
+            match range.update(&data) {
+                Some(GainStep::Down) => self.apply_gain(range, &mut delay, us)?,
+                Some(GainStep::Up) => {
+                    self.apply_gain(range, &mut delay, us)?;
+                    return Ok(data);
+                }
+                None => return Ok(data),
+            }
+        }
+    }
+
+    /// Write the gain of `range` and its correction, then discard the settling conversions
+    fn apply_gain<DELAY>(
+        &mut self,
+        range: &AutoRange,
+        delay: &mut DELAY,
+        us: u32,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        self.write_gain1(range.gain, range.table.as_ref())?;
+        for _ in 0..range.settle {
+            while !self.drdy()? {
+                delay.delay_us(us);
+            }
+            self.read_adc1()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AutoRange, GainStep};
+    use crate::{
+        device::{Data, Status},
+        registers::{InpMux, InpMuxMuxx, Mode2Gain},
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_update() {
+        let mut range = AutoRange::new(Mode2Gain::None, Mode2Gain::Gain4);
+        let small = Data::new(Status(0), 1 << 28, 2_500_000, 1);
+        assert_eq!(range.update(&small), None);
+        assert_eq!(range.update(&small), Some(GainStep::Up));
+        assert_eq!(range.gain(), 2);
+
+        // Between thresholds resets the hold count
+        let mid = Data::new(Status(0), 1 << 30, 2_500_000, 2);
+        assert_eq!(range.update(&small), None);
+        assert_eq!(range.update(&mid), None);
+        assert_eq!(range.update(&small), None);
+
+        let alarm = Data::new(Status(1 << 2), 1 << 30, 2_500_000, 2);
+        assert_eq!(range.update(&alarm), Some(GainStep::Down));
+        assert_eq!(range.update(&alarm), None);
+        assert_eq!(range.gain(), 1);
+    }
+
+    #[test]
+    fn test_read_autoranged() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 10_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.start1().unwrap();
+
+        let mut range = AutoRange::new(Mode2Gain::None, Mode2Gain::Gain32);
+        let mut data = adc.read_autoranged(&mut range, NoDelay, 10).unwrap();
+        for _ in 0..20 {
+            data = adc.read_autoranged(&mut range, NoDelay, 10).unwrap();
+        }
+        assert_eq!(data.gain, 32);
+        assert_eq!(data.to_microvolts(), 10_000);
+
+        // Overrange readings are repeated at lower gain
+        sim.set_input(InpMuxMuxx::Ain0, 1_000_000);
+        let data = adc.read_autoranged(&mut range, NoDelay, 10).unwrap();
+        assert_eq!(data.gain, 2);
+        assert_eq!(data.to_microvolts(), 1_000_000);
+    }
+}
@@ -0,0 +1,184 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    processing::{Ema, Stage},
+    registers::{Adc2CfgDr, Adc2Mux},
+};
+
+use super::{ads1263::_Ads1263, Ads126x, Ads126xError, Data, Status};
+
+/// Polarity-swap auto-zero of ADC2 readings, see [Ads126x::read_adc2_autozero]
+///
+/// ADC2 has no chop mode, so its offset is removed in software: each reading converts the input
+/// with both polarities of ADC2MUX and returns (A − B) / 2, while (A + B) / 2 is the offset. The
+/// offset estimate is averaged over about 2^`shift` readings.
+#[derive(Debug, Clone)]
+pub struct AutoZero {
+    /// Conversions discarded after each polarity swap
+    ///
+    /// Writing ADC2MUX restarts the conversion, so the first new data is settled. The default of
+    /// one also discards a conversion that completed before the swap took effect.
+    pub settle: u8,
+    estimate: Ema,
+    offset: Option<i32>,
+    /// ADC2MUX with the polarity selected by the user
+    mux: Option<u8>,
+}
+
+impl AutoZero {
+    pub fn new(shift: u32) -> Self {
+        Self {
+            settle: 1,
+            estimate: Ema::new(shift),
+            offset: None,
+            mux: None,
+        }
+    }
+
+    /// Averaged ADC2 offset in codes (left aligned to 32 bits), `None` before the first reading
+    pub fn offset(&self) -> Option<i32> {
+        self.offset
+    }
+
+    /// Forget the offset estimate, e.g. after a change of gain or data rate
+    pub fn reset(&mut self) {
+        self.estimate.reset();
+        self.offset = None;
+        self.mux = None;
+    }
+
+    /// ADC2 conversions per reading, both polarities and the discarded conversions
+    pub fn conversions(&self) -> u32 {
+        2 + u32::from(self.settle)
+    }
+
+    /// Approximate duration of a reading at data rate `dr` in us
+    ///
+    /// The first conversion after a swap takes about three conversion periods as the sinc3 filter
+    /// of ADC2 settles.
+    pub fn reading_time_us(&self, dr: Adc2CfgDr) -> u32 {
+        let period_us = match dr {
+            Adc2CfgDr::Sps10 => 100_000,
+            Adc2CfgDr::Sps100 => 10_000,
+            Adc2CfgDr::Sps400 => 2_500,
+            Adc2CfgDr::Sps800 => 1_250,
+        };
+        period_us * (1 + 3 + u32::from(self.settle))
+    }
+
+    /// Combine conversion `a` with the selected polarity and `b` with swapped polarity
+    ///
+    /// The status of the result holds the alarms of both conversions.
+    pub fn update(&mut self, a: Data, b: Data) -> Data {
+        let (a_code, b_code) = (i64::from(a.code), i64::from(b.code));
+        if let Some(offset) = self.estimate.push((a_code + b_code) / 2) {
+            self.offset = Some(offset as i32);
+        }
+        Data {
+            status: Status(a.status.0 | b.status.0),
+            code: ((a_code - b_code) / 2) as i32,
+            ..a
+        }
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr>
+    Ads126x<SPI, CS, DRDY, RST, _Ads1263, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+{
+    /// Read ADC2 with polarity-swap auto-zero, polling every `us` microseconds
+    ///
+    /// ADC2 must be converting with the status byte enabled in
+    /// [Interface](crate::registers::Interface), new data is detected with
+    /// [Status::adc2](super::Status::adc2). The polarity is swapped once per reading, so ADC2MUX
+    /// may be left swapped afterwards. Writing a different ADC2MUX starts a new offset estimate.
+    pub fn read_adc2_autozero<DELAY>(
+        &mut self,
+        az: &mut AutoZero,
+        mut delay: DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let current = self.state.regs.get::<Adc2Mux>().0;
+        let (mux, swapped) = match az.mux {
+            Some(mux) if mux == current => (mux, false),
+            Some(mux) if mux.rotate_left(4) == current => (mux, true),
+            _ => {
+                az.reset();
+                az.mux = Some(current);
+                (current, false)
+            }
+        };
+
+        let first = self.next_adc2(&mut delay, us)?;
+        let next = if swapped { mux } else { mux.rotate_left(4) };
+        self.write_reg(Adc2Mux(next))?;
+        for _ in 0..az.settle {
+            self.next_adc2(&mut delay, us)?;
+        }
+        let second = self.next_adc2(&mut delay, us)?;
+
+        Ok(if swapped {
+            az.update(second, first)
+        } else {
+            az.update(first, second)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutoZero;
+    use crate::{
+        device::{Data, Status},
+        registers::{Adc2Cfg, Adc2CfgDr, Adc2CfgGain, Adc2CfgRef, Adc2Mux, InpMuxMuxx},
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_read_adc2_autozero() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain2, 100_000);
+        sim.set_input(InpMuxMuxx::Ain3, -50_000);
+        sim.set_offset(120);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.set_intref(true).unwrap();
+        let mux = Adc2Mux::differential(InpMuxMuxx::Ain2, InpMuxMuxx::Ain3);
+        adc.write_reg(Adc2Mux(mux.0)).unwrap();
+        adc.write_reg(Adc2Cfg::with(
+            Adc2CfgDr::Sps100,
+            Adc2CfgRef::InternalRef,
+            Adc2CfgGain::None,
+        ))
+        .unwrap();
+        adc.start2().unwrap();
+
+        let mut az = AutoZero::new(2);
+        assert_eq!(az.reading_time_us(Adc2CfgDr::Sps100), 50_000);
+        for i in 0..4 {
+            let data = adc.read_adc2_autozero(&mut az, NoDelay, 10).unwrap();
+            assert!((data.to_microvolts() - 150_000).abs() <= 1);
+            let swapped = adc.registers().get::<Adc2Mux>().0 != mux.0;
+            assert_eq!(swapped, i % 2 == 0);
+        }
+        let offset = Data::new(Status(0), az.offset().unwrap(), 2_500_000, 1);
+        assert!((offset.to_microvolts() - 120).abs() <= 1);
+
+        // A new input pair restarts the estimate
+        adc.write_reg(Adc2Mux::singleended(InpMuxMuxx::Ain2))
+            .unwrap();
+        let data = adc.read_adc2_autozero(&mut az, NoDelay, 10).unwrap();
+        assert!((data.to_microvolts() - 100_000).abs() <= 1);
+    }
+}
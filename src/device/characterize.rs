@@ -0,0 +1,283 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::registers::{
+    InpMux, InpMuxMuxx, Mode1, Mode1Filter, Mode2, Mode2Dr, Mode2Gain, MODE1, TDACP,
+};
+
+use super::{private, Ads126x, Ads126xError};
+
+/// How the ADC1 inputs are shorted for [Ads126x::characterize]
+pub enum Short {
+    /// Both inputs connected to the same pin, e.g. [InpMuxMuxx::AinCom]
+    Pin(InpMuxMuxx),
+    /// Both inputs connected to the TDAC outputs, set to the same voltage (mid supply)
+    Tdac,
+}
+
+/// Conditions of a noise measurement with [Ads126x::characterize]
+pub struct NoiseTest {
+    pub short: Short,
+    pub rate: Mode2Dr,
+    pub filter: Mode1Filter,
+    pub gain: Mode2Gain,
+}
+
+/// Offset and noise of a series of conversions
+///
+/// Voltages are stored in nV so that noise below 1 uV is resolved without floating point, the
+/// `_uv` methods give them in uV.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NoiseStats {
+    pub samples: u32,
+    /// Reference voltage and PGA gain during conversion
+    pub vref_uv: u32,
+    pub gain: u8,
+    /// Mean code (rounded)
+    pub mean_code: i32,
+    pub min_code: i32,
+    pub max_code: i32,
+    /// Standard deviation in codes (rounded down)
+    pub rms_codes: u32,
+    /// Mean offset in nV
+    pub offset_nv: i64,
+    /// RMS noise in nV
+    pub rms_nv: u64,
+    /// Peak-to-peak noise in nV
+    pub peak_to_peak_nv: u64,
+    /// Lowest code of the first histogram bin
+    pub histogram_min: i32,
+    /// Width of each histogram bin in codes
+    pub histogram_bin_codes: u64,
+}
+
+impl NoiseStats {
+    /// Compute statistics of ADC1 `codes` and fill `histogram` with the number of codes in each bin
+    ///
+    /// The bins evenly divide the range between the lowest and highest code.
+    pub fn from_codes(codes: &[i32], vref_uv: u32, gain: u8, histogram: &mut [u32]) -> Self {
+        let n = codes.len().max(1) as i128;
+        let min_code = codes.iter().copied().min().unwrap_or(0);
+        let max_code = codes.iter().copied().max().unwrap_or(0);
+        let sum: i128 = codes.iter().map(|c| i128::from(*c)).sum();
+        let mean = (2 * sum + sum.signum() * n) / (2 * n);
+        let var = codes
+            .iter()
+            .map(|c| (i128::from(*c) - mean).pow(2))
+            .sum::<i128>()
+            / n;
+        let rms_codes = (var as u128).isqrt() as u32;
+
+        let span = (i64::from(max_code) - i64::from(min_code) + 1) as u64;
+        let bin_codes = span.div_ceil(histogram.len().max(1) as u64);
+        histogram.fill(0);
+        for c in codes {
+            let bin = (i64::from(*c) - i64::from(min_code)) as u64 / bin_codes;
+            if let Some(b) = histogram.get_mut(bin as usize) {
+                *b += 1;
+            }
+        }
+
+        // nV per code is vref / gain / 2^31
+        let to_nv = |codes: i128| {
+            let d = i128::from(gain.max(1)) << 31;
+            let x = codes * i128::from(vref_uv) * 1000;
+            ((x.abs() + d / 2) / d * x.signum()) as i64
+        };
+        Self {
+            samples: codes.len() as u32,
+            vref_uv,
+            gain,
+            mean_code: mean as i32,
+            min_code,
+            max_code,
+            rms_codes,
+            offset_nv: to_nv(mean),
+            rms_nv: to_nv(rms_codes.into()) as u64,
+            peak_to_peak_nv: to_nv(i128::from(max_code) - i128::from(min_code)) as u64,
+            histogram_min: min_code,
+            histogram_bin_codes: bin_codes,
+        }
+    }
+
+    /// Peak-to-peak noise in codes
+    pub fn peak_to_peak_codes(&self) -> u32 {
+        self.max_code.abs_diff(self.min_code)
+    }
+
+    /// Mean offset in uV
+    #[cfg(feature = "float")]
+    pub fn offset_uv(&self) -> f64 {
+        self.offset_nv as f64 / 1000.0
+    }
+
+    /// RMS noise in uV
+    #[cfg(feature = "float")]
+    pub fn rms_uv(&self) -> f64 {
+        self.rms_nv as f64 / 1000.0
+    }
+
+    /// Peak-to-peak noise in uV
+    #[cfg(feature = "float")]
+    pub fn peak_to_peak_uv(&self) -> f64 {
+        self.peak_to_peak_nv as f64 / 1000.0
+    }
+
+    /// Effective resolution in bits, log2(full scale range / RMS noise)
+    #[cfg(feature = "float")]
+    pub fn effective_bits(&self) -> f64 {
+        32.0 - libm::log2(f64::from(self.rms_codes.max(1)))
+    }
+
+    /// Noise-free resolution in bits, log2(full scale range / peak-to-peak noise)
+    #[cfg(feature = "float")]
+    pub fn noise_free_bits(&self) -> f64 {
+        32.0 - libm::log2(f64::from(self.peak_to_peak_codes().max(1)))
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Measure ADC1 offset and noise with the inputs shorted internally
+    ///
+    /// Fills `codes` with conversions polling DRDY every `us` microseconds and `histogram` with
+    /// their distribution, see [NoiseStats::from_codes]. MODE1, MODE2, INPMUX and the TDACs are
+    /// restored afterwards and ADC1 is stopped.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn characterize<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+        test: NoiseTest,
+        codes: &mut [i32],
+        histogram: &mut [u32],
+    ) -> Result<NoiseStats, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        // MODE1, MODE2, INPMUX and TDACP, TDACN
+        let mut modes = [0; 3];
+        let mut tdacs = [0; 2];
+        self.read_regs(MODE1, &mut modes)?;
+        self.read_regs(TDACP, &mut tdacs)?;
+
+        let mut mode1 = Mode1(modes[0]);
+        mode1.set_filter(test.filter as u8);
+        let mut mode2 = Mode2(modes[1]);
+        mode2.set_dr(test.rate as u8);
+        mode2.set_gain(test.gain as u8);
+        let inpmux = match test.short {
+            Short::Pin(pin) => InpMux::differential(pin, pin),
+            Short::Tdac => {
+                self.write_regs(TDACP, &[0, 0])?;
+                InpMux::tdac()
+            }
+        };
+        self.write_regs(MODE1, &[mode1.0, mode2.0, inpmux.0])?;
+
+        let res = self.start1().and_then(|_| {
+            for code in codes.iter_mut() {
+                while !self.drdy()? {
+                    delay.delay_us(us);
+                }
+                *code = self.read_adc1()?.code;
+            }
+            Ok(())
+        });
+
+        let restore = self
+            .stop1()
+            .and_then(|_| self.write_regs(MODE1, &modes))
+            .and_then(|_| self.write_regs(TDACP, &tdacs));
+        res.and(restore)?;
+
+        Ok(NoiseStats::from_codes(
+            codes,
+            self.state.vref1_uv,
+            mode2.pga_gain(),
+            histogram,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NoiseStats, NoiseTest, Short};
+    use crate::{
+        registers::{InpMuxMuxx, Mode1Filter, Mode2, Mode2Dr, Mode2Gain},
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_stats() {
+        let mut histogram = [0; 4];
+        let stats = NoiseStats::from_codes(&[-2, -1, 0, 1, 2, 0], 2_500_000, 1, &mut histogram);
+        assert_eq!((stats.mean_code, stats.rms_codes), (0, 1));
+        assert_eq!(stats.peak_to_peak_codes(), 4);
+        assert_eq!(stats.histogram_bin_codes, 2);
+        assert_eq!(histogram, [2, 3, 1, 0]);
+        // 1 code is 1.164 nV at 2.5 V
+        assert_eq!(stats.rms_nv, 1);
+        assert_eq!(stats.peak_to_peak_nv, 5);
+        #[cfg(feature = "float")]
+        assert_eq!((stats.rms_uv(), stats.peak_to_peak_uv()), (0.001, 0.005));
+
+        // Saturated input spanning the whole code range
+        for bins in [0, 1] {
+            let mut histogram = [0; 1];
+            let stats = NoiseStats::from_codes(
+                &[i32::MIN, 0, i32::MAX],
+                2_500_000,
+                1,
+                &mut histogram[..bins],
+            );
+            assert_eq!(stats.histogram_bin_codes, 1 << 32);
+            assert_eq!(stats.peak_to_peak_codes(), u32::MAX);
+        }
+        let mut histogram = [0; 2];
+        NoiseStats::from_codes(&[i32::MIN, i32::MAX], 2_500_000, 1, &mut histogram);
+        assert_eq!(histogram, [1, 1]);
+    }
+
+    #[test]
+    fn test_characterize() {
+        let sim = SimAds126x::ads1263();
+        sim.set_offset(50);
+        sim.set_noise(1000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+
+        let mut codes = [0; 64];
+        let mut histogram = [0; 8];
+        for short in [Short::Pin(InpMuxMuxx::AinCom), Short::Tdac] {
+            let test = NoiseTest {
+                short,
+                rate: Mode2Dr::Sps400,
+                filter: Mode1Filter::Sinc4,
+                gain: Mode2Gain::Gain8,
+            };
+            let stats = adc
+                .characterize(NoDelay, 10, test, &mut codes, &mut histogram)
+                .unwrap();
+            assert_eq!(stats.gain, 8);
+            assert!((stats.offset_nv - 50_000).abs() < 100);
+            #[cfg(feature = "float")]
+            assert!((stats.offset_uv() - 50.0).abs() < 0.1);
+            assert!(stats.rms_codes > 0 && stats.rms_codes < 1000);
+            assert_eq!(histogram.iter().sum::<u32>(), 64);
+        }
+        assert_eq!(sim.registers().get::<Mode2>().0, 0x04);
+        assert!(!sim.adc1_running());
+    }
+}
@@ -0,0 +1,138 @@
+use core::fmt;
+
+use embedded_hal_1::spi::ErrorKind;
+
+/// Maximum length of a data frame following the RDATA command (status, 4 data bytes and CRC)
+pub const MAX_FRAME_LEN: usize = 6;
+
+/// Details of a checksum/CRC mismatch during a data read
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrcError {
+    /// Checksum/CRC calculated from the received data bytes
+    pub expected: u8,
+    /// Checksum/CRC byte sent by the device
+    pub actual: u8,
+    frame: [u8; MAX_FRAME_LEN],
+    len: usize,
+}
+
+impl CrcError {
+    pub(crate) fn new(expected: u8, actual: u8, frame: &[u8]) -> Self {
+        let len = frame.len().min(MAX_FRAME_LEN);
+        let mut buf = [0; MAX_FRAME_LEN];
+        buf[..len].copy_from_slice(&frame[..len]);
+        Self {
+            expected,
+            actual,
+            frame: buf,
+            len,
+        }
+    }
+
+    /// Raw frame received from the device (status byte if enabled, data bytes and CRC byte)
+    pub fn frame(&self) -> &[u8] {
+        &self.frame[..self.len]
+    }
+}
+
+impl fmt::Display for CrcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {:#04x}, got {:#04x} (frame {:02x?})",
+            self.expected,
+            self.actual,
+            self.frame()
+        )
+    }
+}
+
+/// Adc error
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Ads126xError<SpiErr, IoErr> {
+    /// Error during SPI communication
+    SpiErr(SpiErr),
+    /// Error setting/getting io state
+    IoErr(IoErr),
+    /// Crc error during read
+    Crc(CrcError),
+    /// No device responded, the ID register read back as all zeros or all ones
+    NoDevice,
+    /// Device reported an unexpected device ID (raw [crate::registers::Id] byte)
+    WrongDevice(u8),
+    /// Device reported an unsupported revision (raw [crate::registers::Id] byte)
+    UnsupportedRevision(u8),
+    /// Operation is not supported by this device
+    Unsupported,
+    /// Device reset unexpectedly, the configuration was restored and the data was discarded
+    DeviceReset,
+    /// No register at this address, or a register range extends past the last register
+    InvalidRegister(u8),
+    /// Register read back differently than last written
+    RegisterMismatch { addr: u8, expected: u8, actual: u8 },
+    /// Some other error
+    Other,
+}
+
+impl<SpiErr, IoErr> Ads126xError<SpiErr, IoErr> {
+    /// Map error into an embedded-hal [ErrorKind]
+    ///
+    /// Errors from the underlying bus/pins are opaque and map to [ErrorKind::Other],
+    /// corrupted frames map to [ErrorKind::FrameFormat].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Ads126xError::Crc(_) => ErrorKind::FrameFormat,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl<SpiErr, IoErr> fmt::Display for Ads126xError<SpiErr, IoErr>
+where
+    SpiErr: fmt::Debug,
+    IoErr: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ads126xError::SpiErr(e) => write!(f, "SPI error: {e:?}"),
+            Ads126xError::IoErr(e) => write!(f, "IO error: {e:?}"),
+            Ads126xError::Crc(e) => write!(f, "CRC error: {e}"),
+            Ads126xError::NoDevice => write!(f, "no device responding"),
+            Ads126xError::WrongDevice(id) => write!(f, "unexpected device (ID = {id:#04x})"),
+            Ads126xError::UnsupportedRevision(id) => {
+                write!(f, "unsupported device revision (ID = {id:#04x})")
+            }
+            Ads126xError::Unsupported => write!(f, "operation not supported by device"),
+            Ads126xError::InvalidRegister(addr) => write!(f, "no register at {addr:#04x}"),
+            Ads126xError::DeviceReset => write!(f, "device reset, configuration restored"),
+            Ads126xError::RegisterMismatch {
+                addr,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "register {addr:#04x} reads {actual:#04x}, expected {expected:#04x}"
+            ),
+            Ads126xError::Other => write!(f, "unexpected error"),
+        }
+    }
+}
+
+impl<SpiErr, IoErr> core::error::Error for Ads126xError<SpiErr, IoErr>
+where
+    SpiErr: fmt::Debug,
+    IoErr: fmt::Debug,
+{
+}
+
+impl<SpiErr, IoErr> embedded_hal_1::spi::Error for Ads126xError<SpiErr, IoErr>
+where
+    SpiErr: fmt::Debug,
+    IoErr: fmt::Debug,
+{
+    fn kind(&self) -> ErrorKind {
+        Ads126xError::kind(self)
+    }
+}
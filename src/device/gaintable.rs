@@ -0,0 +1,453 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    processing::div_round,
+    registers::{
+        Adc2Cfg, Adc2CfgGain, Adc2Mux, Calibration, InpMux, InpMuxMuxx, Mode2, Mode2Gain, ADC2CFG,
+        ADC2OFC0, MODE2, OFCAL0,
+    },
+};
+
+use super::{ads1263::_Ads1263, private, Ads126x, Ads126xError, Data};
+
+/// Offset and full-scale correction of one PGA gain step
+///
+/// The values are in the units of the calibration registers: OFCAL/FSCAL for ADC1 and
+/// ADC2OFC/ADC2FSC for ADC2, see [Calibration].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GainCal {
+    pub offset: i32,
+    pub full_scale: u32,
+}
+
+impl GainCal {
+    /// ADC1 correction from the mean codes of a shorted input (`zero`) and of a known input
+    /// (`input`) measured without calibration, and the code `expected` for the known input
+    pub fn adc1(zero: i32, input: i32, expected: i32) -> Self {
+        let offset = div_round(zero.into(), 1 << 8).clamp(-(1 << 23), (1 << 23) - 1);
+        let span = i64::from(input) - (offset << 8);
+        let full_scale = if span == 0 {
+            0x40_0000
+        } else {
+            div_round(0x40_0000 * i128::from(expected), span.into()).clamp(0, 0xFF_FFFF)
+        };
+        Self {
+            offset: offset as i32,
+            full_scale: full_scale as u32,
+        }
+    }
+
+    /// ADC2 correction, see [Self::adc1], codes are left aligned to 32 bits as in [Data::code]
+    pub fn adc2(zero: i32, input: i32, expected: i32) -> Self {
+        let offset = div_round(zero.into(), 1 << 8).clamp(i16::MIN.into(), i16::MAX.into());
+        let span = div_round(input.into(), 1 << 8) - offset;
+        let full_scale = if span == 0 {
+            0x4000
+        } else {
+            div_round(
+                0x4000 * i128::from(div_round(expected.into(), 1 << 8)),
+                span.into(),
+            )
+            .clamp(0, 0xFFFF)
+        };
+        Self {
+            offset: offset as i32,
+            full_scale: full_scale as u32,
+        }
+    }
+
+    /// Correct an ADC1 code converted with OFCAL/FSCAL at their defaults, as the device would
+    pub fn correct1(&self, code: i32) -> i32 {
+        let code = (i64::from(code) - (i64::from(self.offset) << 8)) * i64::from(self.full_scale)
+            / 0x40_0000;
+        code.clamp(i32::MIN.into(), i32::MAX.into()) as i32
+    }
+
+    /// Correct an ADC2 code converted with ADC2OFC/ADC2FSC at their defaults, as the device would
+    pub fn correct2(&self, code: i32) -> i32 {
+        let code =
+            (i64::from(code >> 8) - i64::from(self.offset)) * i64::from(self.full_scale) / 0x4000;
+        (code.clamp(-(1 << 23), (1 << 23) - 1) as i32) << 8
+    }
+}
+
+/// Correction of every PGA gain step of ADC1 ([Mode2Gain]) and ADC2 ([Adc2CfgGain])
+///
+/// The device holds a single gain correction per ADC. Fill the table with
+/// [Ads126x::calibrate_gain1] and [Ads126x::calibrate_gain2] and apply it either by writing the
+/// calibration registers with each gain change ([Ads126x::set_gain1], [Ads126x::set_gain2],
+/// [AutoRange::with_table](super::AutoRange::with_table)) or in software with [Self::correct1] and
+/// [Self::correct2] while the calibration registers are at their defaults.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GainTable {
+    adc1: [Option<GainCal>; 6],
+    adc2: [Option<GainCal>; 8],
+}
+
+impl GainTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Correction of an ADC1 gain step, `None` if not calibrated
+    pub fn adc1(&self, gain: Mode2Gain) -> Option<GainCal> {
+        self.adc1[gain as usize]
+    }
+
+    pub fn set_adc1(&mut self, gain: Mode2Gain, cal: GainCal) {
+        self.adc1[gain as usize] = Some(cal);
+    }
+
+    /// Correction of an ADC2 gain step, `None` if not calibrated
+    pub fn adc2(&self, gain: Adc2CfgGain) -> Option<GainCal> {
+        self.adc2[gain as usize]
+    }
+
+    pub fn set_adc2(&mut self, gain: Adc2CfgGain, cal: GainCal) {
+        self.adc2[gain as usize] = Some(cal);
+    }
+
+    /// Correct ADC1 data for the gain it was converted with, unchanged if that gain is not calibrated
+    pub fn correct1(&self, data: Data) -> Data {
+        match self.adc1.get(data.gain.trailing_zeros() as usize) {
+            Some(Some(cal)) => Data {
+                code: cal.correct1(data.code),
+                ..data
+            },
+            _ => data,
+        }
+    }
+
+    /// Correct ADC2 data for the gain it was converted with, unchanged if that gain is not calibrated
+    pub fn correct2(&self, data: Data) -> Data {
+        match self.adc2.get(data.gain.trailing_zeros() as usize) {
+            Some(Some(cal)) => Data {
+                code: cal.correct2(data.code),
+                ..data
+            },
+            _ => data,
+        }
+    }
+}
+
+/// Inputs used to calibrate a gain step with [Ads126x::calibrate_gain1] or [Ads126x::calibrate_gain2]
+///
+/// The known input is e.g. an external reference, it must stay within the input range at the
+/// calibrated gain.
+#[derive(Debug, Copy, Clone)]
+pub struct CalInputs {
+    /// Positive and negative input with zero differential voltage, e.g. both [InpMuxMuxx::AinCom]
+    pub zero: (InpMuxMuxx, InpMuxMuxx),
+    /// Positive and negative input of the known voltage
+    pub input: (InpMuxMuxx, InpMuxMuxx),
+    /// Known voltage in uV
+    pub input_uv: i64,
+    /// Conversions averaged for each measurement
+    pub samples: u16,
+}
+
+/// Code expected for `uv` at `gain` with a reference of `vref_uv`
+fn expected_code(uv: i64, gain: u8, vref_uv: u32) -> i32 {
+    let code = div_round(
+        i128::from(uv * i64::from(gain)) << 31,
+        vref_uv.max(1).into(),
+    );
+    code.clamp(i32::MIN.into(), i32::MAX.into()) as i32
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Set the ADC1 PGA gain and write its correction from `table` into OFCAL/FSCAL
+    ///
+    /// The calibration registers are left unchanged if the gain is not in the table.
+    pub fn set_gain1(
+        &mut self,
+        gain: Mode2Gain,
+        table: &GainTable,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.write_gain1(gain as u8, Some(table))
+    }
+
+    /// Write the [Mode2::gain] setting and its correction if in `table`
+    pub(crate) fn write_gain1(
+        &mut self,
+        gain: u8,
+        table: Option<&GainTable>,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let mut mode2 = self.state.regs.get::<Mode2>();
+        mode2.set_gain(gain);
+        self.write_reg(mode2)?;
+        if let Some(cal) = table.and_then(|t| t.adc1.get(usize::from(gain)).copied().flatten()) {
+            let mut regs = self.state.regs.calibration();
+            regs.ofcal1 = cal.offset;
+            regs.fscal1 = cal.full_scale;
+            self.write_regs(OFCAL0, &regs.to_bytes().0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Measure the ADC1 offset and gain correction of `gain`, polling DRDY every `us` microseconds
+    ///
+    /// Converts the zero and the known input with OFCAL/FSCAL at their defaults. MODE2, INPMUX and
+    /// the calibration registers are restored afterwards and ADC1 is stopped.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn calibrate_gain1<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+        gain: Mode2Gain,
+        inputs: &CalInputs,
+    ) -> Result<GainCal, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        // MODE2, INPMUX and OFCAL0..FSCAL2
+        let mut modes = [0; 2];
+        let mut cal = [0; 6];
+        self.read_regs(MODE2, &mut modes)?;
+        self.read_regs(OFCAL0, &mut cal)?;
+
+        let mut mode2 = Mode2(modes[0]);
+        mode2.set_gain(gain as u8);
+        let zero = InpMux::differential(inputs.zero.0, inputs.zero.1);
+        self.write_regs(MODE2, &[mode2.0, zero.0])?;
+        self.write_regs(OFCAL0, &Calibration::default().to_bytes().0)?;
+
+        let res = self.start1().and_then(|_| {
+            let zero = self.mean_adc1(&mut delay, us, inputs.samples)?;
+            self.write_reg(InpMux::differential(inputs.input.0, inputs.input.1))?;
+            let input = self.mean_adc1(&mut delay, us, inputs.samples)?;
+            Ok((zero, input))
+        });
+
+        let restore = self
+            .stop1()
+            .and_then(|_| self.write_regs(MODE2, &modes))
+            .and_then(|_| self.write_regs(OFCAL0, &cal));
+        let (zero, input) = res.and_then(|r| restore.map(|_| r))?;
+
+        let expected = expected_code(inputs.input_uv, mode2.pga_gain(), self.state.vref1_uv);
+        Ok(GainCal::adc1(zero, input, expected))
+    }
+
+    /// Mean of `samples` ADC1 codes after discarding the first conversion
+    fn mean_adc1<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+        samples: u16,
+    ) -> Result<i32, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let mut sum = 0;
+        for i in 0..=samples.max(1) {
+            while !self.drdy()? {
+                delay.delay_us(us);
+            }
+            let code = self.read_adc1()?.code;
+            if i > 0 {
+                sum += i64::from(code);
+            }
+        }
+        Ok(div_round(sum.into(), samples.max(1).into()) as i32)
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr>
+    Ads126x<SPI, CS, DRDY, RST, _Ads1263, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+{
+    /// Set the ADC2 PGA gain and write its correction from `table` into ADC2OFC/ADC2FSC
+    ///
+    /// The calibration registers are left unchanged if the gain is not in the table.
+    pub fn set_gain2(
+        &mut self,
+        gain: Adc2CfgGain,
+        table: &GainTable,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let cal = table.adc2(gain);
+        let mut adc2cfg = self.state.regs.get::<Adc2Cfg>();
+        adc2cfg.set_gain2(gain as u8);
+        self.write_reg(adc2cfg)?;
+        if let Some(cal) = cal {
+            let mut regs = self.state.regs.calibration();
+            regs.ofcal2 = cal.offset as i16;
+            regs.fscal2 = cal.full_scale as u16;
+            self.write_regs(ADC2OFC0, &regs.to_bytes().1)?;
+        }
+        Ok(())
+    }
+
+    /// Measure the ADC2 offset and gain correction of `gain`, polling every `us` microseconds
+    ///
+    /// New ADC2 data is detected with [Status::adc2](super::Status::adc2), so the status byte must
+    /// be enabled in [Interface](crate::registers::Interface). ADC2CFG, ADC2MUX and the ADC2
+    /// calibration registers are restored afterwards and ADC2 is stopped.
+    pub fn calibrate_gain2<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+        gain: Adc2CfgGain,
+        inputs: &CalInputs,
+    ) -> Result<GainCal, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        // ADC2CFG, ADC2MUX and ADC2OFC0..ADC2FSC1
+        let mut cfg = [0; 2];
+        let mut cal = [0; 4];
+        self.read_regs(ADC2CFG, &mut cfg)?;
+        self.read_regs(ADC2OFC0, &mut cal)?;
+
+        let mut adc2cfg = Adc2Cfg(cfg[0]);
+        adc2cfg.set_gain2(gain as u8);
+        let zero = Adc2Mux::differential(inputs.zero.0, inputs.zero.1);
+        self.write_regs(ADC2CFG, &[adc2cfg.0, zero.0])?;
+        self.write_regs(ADC2OFC0, &Calibration::default().to_bytes().1)?;
+
+        let res = self.start2().and_then(|_| {
+            let zero = self.mean_adc2(&mut delay, us, inputs.samples)?;
+            self.write_reg(Adc2Mux::differential(inputs.input.0, inputs.input.1))?;
+            let input = self.mean_adc2(&mut delay, us, inputs.samples)?;
+            Ok((zero, input))
+        });
+
+        let restore = self
+            .stop2()
+            .and_then(|_| self.write_regs(ADC2CFG, &cfg))
+            .and_then(|_| self.write_regs(ADC2OFC0, &cal));
+        let (zero, input) = res.and_then(|r| restore.map(|_| r))?;
+
+        let expected = expected_code(inputs.input_uv, adc2cfg.pga_gain(), self.state.vref2_uv);
+        Ok(GainCal::adc2(zero, input, expected))
+    }
+
+    /// Mean of `samples` new ADC2 codes after discarding the first conversion
+    fn mean_adc2<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+        samples: u16,
+    ) -> Result<i32, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let mut sum = 0;
+        for i in 0..=samples.max(1) {
+            let code = self.next_adc2(delay, us)?.code;
+            if i > 0 {
+                sum += i64::from(code);
+            }
+        }
+        Ok(div_round(sum.into(), samples.max(1).into()) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CalInputs, GainCal, GainTable};
+    use crate::{
+        device::{Data, Status},
+        registers::{
+            Adc2CfgGain, Adc2Mux, Calibration, InpMux, InpMuxMuxx, Mode2, Mode2Dr, Mode2Gain,
+        },
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_gain_cal() {
+        // 1 % gain error and an offset of 1000 codes
+        let cal = GainCal::adc1(1000, 1_010_000_000 + 1000, 1_000_000_000);
+        assert_eq!(cal.offset, 4);
+        assert!((i64::from(cal.correct1(505_000_000 + 1000)) - 500_000_000).abs() < 1000);
+
+        let mut table = GainTable::new();
+        table.set_adc1(Mode2Gain::Gain4, cal);
+        let data = Data::new(Status(0), 505_001_000, 2_500_000, 4);
+        assert!((table.correct1(data).code - 500_000_000).abs() < 1000);
+        let data = Data::new(Status(0), 505_001_000, 2_500_000, 2);
+        assert_eq!(table.correct1(data).code, 505_001_000);
+    }
+
+    #[test]
+    fn test_calibrate_gains() {
+        let sim = SimAds126x::ads1263();
+        sim.set_offset(50);
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.set_intref(true).unwrap();
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::None, false))
+            .unwrap();
+
+        let inputs = CalInputs {
+            zero: (InpMuxMuxx::AinCom, InpMuxMuxx::AinCom),
+            input: (InpMuxMuxx::Ain0, InpMuxMuxx::AinCom),
+            input_uv: 100_000,
+            samples: 4,
+        };
+        let mut table = GainTable::new();
+        for gain in [Mode2Gain::Gain8, Mode2Gain::Gain16] {
+            let cal = adc.calibrate_gain1(NoDelay, 10, gain, &inputs).unwrap();
+            table.set_adc1(gain, cal);
+        }
+        let cal = adc
+            .calibrate_gain2(NoDelay, 10, Adc2CfgGain::Gain4, &inputs)
+            .unwrap();
+        table.set_adc2(Adc2CfgGain::Gain4, cal);
+        assert_eq!(adc.registers().get::<Mode2>().gain(), 0);
+        assert_eq!(adc.read_calibration().unwrap(), Calibration::default());
+
+        // Software correction with default calibration registers
+        sim.set_input(InpMuxMuxx::Ain0, 120_000);
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false))
+            .unwrap();
+        adc.start1().unwrap();
+        let data = adc.read_adc1().unwrap();
+        assert_eq!(data.to_microvolts(), 120_050);
+        assert!((table.correct1(data).to_microvolts() - 120_000).abs() <= 1);
+
+        // Correction written with the gain
+        adc.set_gain1(Mode2Gain::Gain16, &table).unwrap();
+        assert_ne!(adc.registers().calibration(), Calibration::default());
+        let data = adc.read_adc1().unwrap();
+        assert_eq!(data.gain, 16);
+        assert!((data.to_microvolts() - 120_000).abs() <= 1);
+
+        adc.write_reg(Adc2Mux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.set_gain2(Adc2CfgGain::Gain4, &table).unwrap();
+        adc.start2().unwrap();
+        let data = adc.read_adc2().unwrap();
+        assert_eq!(data.gain, 4);
+        assert!((data.to_microvolts() - 120_000).abs() <= 10);
+    }
+}
@@ -2,7 +2,12 @@ use core::marker::PhantomData;
 
 use crate::commands::Command;
 use crate::crc8::{checksum, crc_8_atm};
-use crate::registers::{Interface, Register, FSCAL0, OFCAL0};
+use crate::fmt::RegisterValue;
+use crate::registers::{
+    Adc2Cfg, Calibration, Id, IdDev, Interface, Mode2, Power, Register, RegisterMap, ADC2OFC0,
+    FSCAL0, OFCAL0, REGISTER_COUNT, SUPPORTED_REVISIONS,
+};
+use crate::{PwdnNoConnection, StartNoConnection};
 
 use bitfield::bitfield;
 use embedded_hal::{
@@ -15,9 +20,35 @@ use embedded_hal::{
 
 pub(crate) mod ads1262;
 pub(crate) mod ads1263;
+mod any;
+mod autorange;
+mod autozero;
+mod characterize;
+mod error;
+mod gaintable;
+mod monitor;
+mod power;
+mod recalibrate;
+mod recovery;
+mod reference;
+mod selftest;
+
+pub use any::AnyAds126x;
+pub use autorange::{AutoRange, GainStep};
+pub use autozero::AutoZero;
+pub use characterize::{NoiseStats, NoiseTest, Short};
+pub use error::{Ads126xError, CrcError};
+pub use gaintable::{CalInputs, GainCal, GainTable};
+pub use monitor::{Health, HealthReading, Monitor, Monitored};
+pub use power::{DutyCycle, OptionalPin, SupplyCurrent};
+pub(crate) use private::Sealed;
+pub use recalibrate::{RecalEvent, RecalTrigger, Recalibration};
+pub use reference::Reference;
+pub use selftest::{Check, Loopback, SelfTestLimits, SelfTestReport};
 
 bitfield! {
     /// Status byte returned during a read (if enabled in [Interface] register)
+    #[derive(Copy, Clone, Default)]
     pub struct Status(u8);
     impl Debug;
     // The fields default to u16
@@ -38,35 +69,102 @@ impl Status {
 }
 
 mod private {
-    pub trait Sealed {}
-    impl Sealed for super::ads1262::_Ads1262 {}
-    impl Sealed for super::ads1263::_Ads1263 {}
+    use crate::registers::IdDev;
+
+    pub trait Sealed {
+        const DEVICE: IdDev;
+    }
+    impl Sealed for super::ads1262::_Ads1262 {
+        const DEVICE: IdDev = IdDev::Ads1262;
+    }
+    impl Sealed for super::ads1263::_Ads1263 {
+        const DEVICE: IdDev = IdDev::Ads1263;
+    }
 }
 
+/// Internal reference voltage in uV
+pub const INTERNAL_REF_UV: u32 = 2_500_000;
+
+/// Minimum START high pulse width is 4 tCLK (~0.55 us with the internal 7.3728 MHz oscillator)
+const START_PULSE_US: u32 = 1;
+/// Minimum RESET low pulse width is 4 tCLK (~0.55 us), rounded up to whole milliseconds
+const RESET_PULSE_MS: u32 = 1;
+/// Delay from reset until the device can be communicated with, 2^16 tCLK (~8.9 ms)
+const RESET_DELAY_US: u32 = 9_000;
+
 /// Data returned from ADC during a read from either ADC1 or ADC2
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Data {
     /// Current ADC status
     pub status: Status,
     /// ADC result code
     pub code: i32,
+    /// Reference voltage in uV when the data was read
+    pub vref_uv: u32,
+    /// PGA gain when the data was read
+    pub gain: u8,
 }
 
 impl Data {
-    /// Convert adc result into a voltage given a refrence voltage and gain
-    pub fn to_voltage(&self, vref: f64, gain: f64) -> f64 {
-        f64::from(self.code) * vref / gain / 2147483648.0 /* 2^31 */
+    /// Convert adc result into a voltage using the reference voltage and gain active during conversion
+    #[cfg(feature = "float")]
+    pub fn to_voltage(&self) -> f64 {
+        f64::from(self.code) * f64::from(self.vref_uv) * 1e-6
+            / f64::from(self.gain.max(1))
+            / 2147483648.0 /* 2^31 */
+    }
+
+    /// Convert adc result into microvolts (rounded) using the reference voltage and gain active during conversion
+    pub fn to_microvolts(&self) -> i64 {
+        code_to_scaled(self.code, self.vref_uv, self.gain, 1)
+    }
+
+    /// Convert adc result into nanovolts (rounded) using the reference voltage and gain active during conversion
+    pub fn to_nanovolts(&self) -> i64 {
+        code_to_scaled(self.code, self.vref_uv, self.gain, 1000)
+    }
+
+    /// Convert a reading of the internal temperature sensor ([InpMux::temperature](crate::registers::InpMux::temperature))
+    /// into milli degrees Celsius.
+    ///
+    /// Uses the typical sensor characteristic of 122.4 mV at 25 C and 420 uV/C, the PGA gain should be 1.
+    pub fn to_millicelsius(&self) -> i32 {
+        let nv = self.to_nanovolts() - 122_400_000;
+        ((nv + nv.signum() * 210) / 420 + 25_000) as i32
     }
 }
 
+/// Convert a code into `vref_uv * scale` units, rounding half away from zero
+fn code_to_scaled(code: i32, vref_uv: u32, gain: u8, scale: i128) -> i64 {
+    let n = i128::from(code) * i128::from(vref_uv) * scale;
+    let d = i128::from(gain.max(1)) << 31;
+    let q = (n.abs() + d / 2) / d;
+    (if n < 0 { -q } else { q }) as i64
+}
+
 impl Data {
-    fn new(status: Status, code: i32) -> Self {
-        Self { status, code }
+    pub(crate) fn new(status: Status, code: i32, vref_uv: u32, gain: u8) -> Self {
+        Self {
+            status,
+            code,
+            vref_uv,
+            gain,
+        }
     }
 }
 
+/// Fail unless `len` registers starting at `start` exist
+fn check_range<SpiErr, IoErr>(start: u8, len: usize) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+    if len == 0 || usize::from(start) + len > REGISTER_COUNT {
+        return Err(Ads126xError::InvalidRegister(start));
+    }
+    Ok(())
+}
+
 /// Generic ADS1263x device. Use [crate::Ads1262] or [crate::Ads1263] instead.
-pub struct Ads126x<SPI, CS, DRDY, RST, X>
+pub struct Ads126x<SPI, CS, DRDY, RST, X, START = StartNoConnection, PWDN = PwdnNoConnection>
 where
     X: private::Sealed,
 {
@@ -75,41 +173,179 @@ where
     cs: CS,
     drdy: DRDY,
     rst: RST,
-    interface: Interface,
+    start: START,
+    pwdn: PWDN,
+    state: State,
 }
 
-/// Adc error
-#[derive(Debug)]
-pub enum Ads126xError<SpiErr, IoErr> {
-    /// Error during SPI communication
-    SpiErr(SpiErr),
-    /// Error setting/getting io state
-    IoErr(IoErr),
-    /// Crc error during read
-    Crc,
-    /// Some other error
-    Other,
+/// Driver state
+#[derive(Default)]
+struct State {
+    /// Registers as last written
+    regs: RegisterMap,
+    /// ADC1 reference voltage in uV
+    vref1_uv: u32,
+    /// ADC2 reference voltage in uV
+    vref2_uv: u32,
+    /// [Power] register saved while in standby
+    standby: Option<Power>,
+    /// ADC1 and ADC2 started by command
+    running: [bool; 2],
+    /// Unexpected resets recovered from
+    resets: u32,
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            vref1_uv: INTERNAL_REF_UV,
+            vref2_uv: INTERNAL_REF_UV,
+            ..Default::default()
+        }
+    }
 }
 
 impl<SPI, CS, DRDY, RST, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X>
 where
     SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
     CS: OutputPin<Error = IoErr>,
-    RST: OutputPin<Error = IoErr>,
     X: private::Sealed,
 {
-    /// Reset device by polling RST low for 100ms
-    pub fn reset<DELAY>(&mut self, mut delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    fn from_parts(spi: SPI, cs: CS, drdy: DRDY, rst: RST) -> Self {
+        Self {
+            _x: PhantomData,
+            spi,
+            cs,
+            drdy,
+            rst,
+            start: StartNoConnection,
+            pwdn: PwdnNoConnection,
+            state: State::new(),
+        }
+    }
+
+    /// Create a new device after verifying the device ID and revision
+    ///
+    /// Fails with [Ads126xError::WrongDevice] if a different ADS126x is connected, use [AnyAds126x::probe]
+    /// to detect which device is connected.
+    pub fn probe(
+        spi: SPI,
+        cs: CS,
+        drdy: DRDY,
+        rst: RST,
+    ) -> Result<Self, Ads126xError<SpiErr, IoErr>> {
+        let mut this = Self::from_parts(spi, cs, drdy, rst);
+        let (id, dev) = this.probe_id()?;
+        if dev != X::DEVICE {
+            return Err(Ads126xError::WrongDevice(id.0));
+        }
+        Ok(this)
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Change device type, registers are kept as-is
+    fn into_device<Y: private::Sealed>(self) -> Ads126x<SPI, CS, DRDY, RST, Y, START, PWDN> {
+        Ads126x {
+            _x: PhantomData,
+            spi: self.spi,
+            cs: self.cs,
+            drdy: self.drdy,
+            rst: self.rst,
+            start: self.start,
+            pwdn: self.pwdn,
+            state: self.state,
+        }
+    }
+
+    /// Use a START pin to start conversions
+    ///
+    /// See [Self::start_high], [Self::start_low] and [Self::pulse_start]
+    pub fn with_start<S>(self, start: S) -> Ads126x<SPI, CS, DRDY, RST, X, S, PWDN>
     where
-        DELAY: DelayMs<u32>,
+        S: OutputPin<Error = IoErr>,
     {
-        self.rst.set_high().map_err(|e| Ads126xError::IoErr(e))?;
-        delay.delay_ms(100);
-        self.rst.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        delay.delay_ms(100);
-        self.rst.set_high().map_err(|e| Ads126xError::IoErr(e))?;
-        delay.delay_ms(100);
-        Ok(())
+        Ads126x {
+            _x: PhantomData,
+            spi: self.spi,
+            cs: self.cs,
+            drdy: self.drdy,
+            rst: self.rst,
+            start,
+            pwdn: self.pwdn,
+            state: self.state,
+        }
+    }
+
+    /// Use a PWDN pin to power down the device
+    ///
+    /// See [Self::power_down] and [Self::power_up]
+    pub fn with_pwdn<P>(self, pwdn: P) -> Ads126x<SPI, CS, DRDY, RST, X, START, P>
+    where
+        P: OutputPin<Error = IoErr>,
+    {
+        Ads126x {
+            _x: PhantomData,
+            spi: self.spi,
+            cs: self.cs,
+            drdy: self.drdy,
+            rst: self.rst,
+            start: self.start,
+            pwdn,
+            state: self.state,
+        }
+    }
+
+    /// Read [Id] register and check that it is a supported device and revision
+    fn probe_id(&mut self) -> Result<(Id, IdDev), Ads126xError<SpiErr, IoErr>> {
+        let id = self.read_reg::<Id>()?;
+        if id.0 == 0x00 || id.0 == 0xFF {
+            return Err(Ads126xError::NoDevice);
+        }
+        let Some(dev) = id.device() else {
+            return Err(Ads126xError::WrongDevice(id.0));
+        };
+        if !SUPPORTED_REVISIONS.contains(&id.rev_id()) {
+            return Err(Ads126xError::UnsupportedRevision(id.0));
+        }
+        Ok((id, dev))
+    }
+
+    /// Run a SPI transaction with CS asserted.
+    ///
+    /// CS is always released afterwards, even if the transaction fails.
+    pub(crate) fn transaction<R, F>(&mut self, f: F) -> Result<R, Ads126xError<SpiErr, IoErr>>
+    where
+        F: FnOnce(&mut SPI) -> Result<R, SpiErr>,
+    {
+        self.cs.set_low().map_err(Ads126xError::IoErr)?;
+        let res = f(&mut self.spi);
+        let cs = self.cs.set_high();
+        let res = res.map_err(Ads126xError::SpiErr)?;
+        cs.map_err(Ads126xError::IoErr)?;
+        Ok(res)
+    }
+
+    /// Registers as last written by the driver
+    pub fn registers(&self) -> &RegisterMap {
+        &self.state.regs
+    }
+
+    /// Read register at `addr` from device
+    pub fn read_reg_at(&mut self, addr: u8) -> Result<u8, Ads126xError<SpiErr, IoErr>> {
+        let mut b = [0];
+        self.read_regs(addr, &mut b)?;
+        Ok(b[0])
+    }
+
+    /// Write `value` to register at `addr`
+    pub fn write_reg_at(&mut self, addr: u8, value: u8) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.write_regs(addr, &[value])
     }
 
     /// Read register from device
@@ -120,11 +356,8 @@ where
     {
         let mut data = [Command::RReg.reg(REG::REG), 0x00, 0x00];
 
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi
-            .transfer(&mut data)
-            .map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+        self.transaction(|spi| spi.transfer(&mut data).map(|_| ()))?;
+        log_in!(RegisterRead, "RREG {:?}", RegisterValue(REG::REG, data[2]));
         Ok(REG::from_byte(data[2]))
     }
 
@@ -134,25 +367,120 @@ where
     where
         REG: Register,
     {
-        let data = [Command::WReg.reg(REG::REG), 0x00, reg.into_byte()];
+        let b = reg.into_byte();
+        let data = [Command::WReg.reg(REG::REG), 0x00, b];
 
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi.write(&data).map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+        self.transaction(|spi| spi.write(&data))?;
+        log_in!(RegisterWrite, "WREG {:?}", RegisterValue(REG::REG, b));
+        self.state.regs.0[REG::REG as usize] = b;
+        Ok(())
+    }
+
+    /// Read consecutive registers starting at `start` into `buf`
+    fn read_regs(&mut self, start: u8, buf: &mut [u8]) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        check_range(start, buf.len())?;
+        let mut data = [0; 2 + REGISTER_COUNT];
+        let data = &mut data[..2 + buf.len()];
+        data[0] = Command::RReg.reg(start);
+        data[1] = buf.len() as u8 - 1;
+
+        self.transaction(|spi| spi.transfer(data).map(|_| ()))?;
+        buf.copy_from_slice(&data[2..]);
+        for (addr, b) in (start..).zip(buf.iter()) {
+            log_in!(RegisterRead, "RREG {:?}", RegisterValue(addr, *b));
+        }
+        Ok(())
+    }
+
+    /// Write `buf` into consecutive registers starting at `start`
+    fn write_regs(&mut self, start: u8, buf: &[u8]) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        check_range(start, buf.len())?;
+        let mut data = [0; 2 + REGISTER_COUNT];
+        let data = &mut data[..2 + buf.len()];
+        data[0] = Command::WReg.reg(start);
+        data[1] = buf.len() as u8 - 1;
+        data[2..].copy_from_slice(buf);
+
+        self.transaction(|spi| spi.write(data))?;
+        for (addr, b) in (start..).zip(buf.iter()) {
+            log_in!(RegisterWrite, "WREG {:?}", RegisterValue(addr, *b));
+        }
+        self.state.regs.0[start as usize..start as usize + buf.len()].copy_from_slice(buf);
+        Ok(())
+    }
+
+    /// Read all registers from device
+    ///
+    /// ADC2 registers are only valid on a ADS1263.
+    pub fn read_registers(&mut self) -> Result<RegisterMap, Ads126xError<SpiErr, IoErr>> {
+        let mut regs = RegisterMap::default();
+        self.read_regs(0, &mut regs.0)?;
+        Ok(regs)
+    }
+
+    /// Read all registers from device into [Self::registers]
+    ///
+    /// Use when the device was configured before the driver was created, e.g. by a previous run of
+    /// the program, so that data frames are decoded according to the actual configuration.
+    ///
+    /// The reference voltage of an ADC on the internal reference is set to 2.5 V. The voltage of an
+    /// external or supply reference can't be read back, call [Self::set_reference] and
+    /// [Self::set_reference2](Ads126x::set_reference2) again when using one.
+    pub fn sync_registers(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.state.regs = self.read_registers()?;
+        self.sync_references();
+        Ok(())
+    }
+
+    /// Read offset and full-scale calibration registers from device
+    ///
+    /// ADC2 calibration is left at its defaults on a ADS1262. The result is also stored in
+    /// [Self::registers] as the calibration commands change these registers on the device.
+    pub fn read_calibration(&mut self) -> Result<Calibration, Ads126xError<SpiErr, IoErr>> {
+        let mut adc1 = [0; 6];
+        self.read_regs(OFCAL0, &mut adc1)?;
+        let (_, mut adc2) = Calibration::default().to_bytes();
+        if X::DEVICE == IdDev::Ads1263 {
+            self.read_regs(ADC2OFC0, &mut adc2)?;
+        }
+        let cal = Calibration::from_bytes(adc1, adc2);
+        self.state.regs.set_calibration(&cal);
+        Ok(cal)
+    }
+
+    /// Write offset and full-scale calibration registers to device
+    ///
+    /// ADC2 calibration is ignored on a ADS1262.
+    pub fn write_calibration(
+        &mut self,
+        cal: &Calibration,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let (adc1, adc2) = cal.to_bytes();
+        self.write_regs(OFCAL0, &adc1)?;
+        if X::DEVICE == IdDev::Ads1263 {
+            self.write_regs(ADC2OFC0, &adc2)?;
+        }
         Ok(())
     }
 
     fn send_command(&mut self, cmd: Command) -> Result<(), Ads126xError<SpiErr, IoErr>> {
         let data = [cmd as u8];
 
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi.write(&data).map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+        self.transaction(|spi| spi.write(&data))?;
+        log_in!(Command, "{:?}", cmd);
+        match cmd {
+            Command::Start1 => self.state.running[0] = true,
+            Command::Stop1 => self.state.running[0] = false,
+            Command::Start2 => self.state.running[1] = true,
+            Command::Stop2 => self.state.running[1] = false,
+            Command::Reset => self.state.running = [false; 2],
+            _ => {}
+        }
         Ok(())
     }
 
     fn read_data(&mut self, cmd: Option<Command>) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
+        let interface = self.state.regs.get::<Interface>();
 
         // Determine length of SPI transfer
         let mut buf = [0; 8];
@@ -161,26 +489,26 @@ where
             len += 1;
             buf[0] = cmd as u8;
         }
-        if self.interface.status() {
+        if interface.status() {
             len += 1;
         }
-        if self.interface.crc() > 0 {
+        if interface.crc() > 0 {
             len += 1;
         }
 
         // SPI transfer
-        let mut resp = self
-            .spi
-            .transfer(&mut buf[..len])
-            .map_err(|e| Ads126xError::SpiErr(e))?;
+        self.transaction(|spi| spi.transfer(&mut buf[..len]).map(|_| ()))?;
 
         // First byte is command if used
-        if cmd.is_some() {
-            resp = &resp[1..];
-        }
+        let frame = if cmd.is_some() {
+            &buf[1..len]
+        } else {
+            &buf[..len]
+        };
+        let mut resp = frame;
 
         // Read status byte if enabled
-        let (status, value) = if self.interface.status() {
+        let (status, value) = if interface.status() {
             let stat = Status(resp[0]);
             let value = &resp[1..5];
             resp = &resp[5..];
@@ -193,41 +521,170 @@ where
         let code = i32::from_be_bytes(value.try_into().map_err(|_| Ads126xError::Other)?);
 
         // Read CRC if enabled
-        if self.interface.crc() > 0 {
+        if interface.crc() > 0 {
             let adc_crc = resp[0];
 
             // Verify checksum
-            let crc = if self.interface.crc() == 1 {
+            let crc = if interface.crc() == 1 {
                 checksum(value)
-            } else if self.interface.crc() == 2 {
+            } else if interface.crc() == 2 {
                 crc_8_atm(value)
             } else {
                 0
             };
 
             if adc_crc != crc {
-                return Err(Ads126xError::Crc);
+                let err = CrcError::new(crc, adc_crc, frame);
+                warn!("{:?} CRC error {:?}", cmd, err);
+                // A reset reverts to the default checksum mode, confirm it from the registers
+                if status.reset() && !self.state.regs.get::<Power>().reset() {
+                    self.check_registers()?;
+                }
+                return Err(Ads126xError::Crc(err));
             }
         }
 
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
-        Ok(Data::new(status, code))
+        // The device forgot its configuration since the reset flag was cleared
+        if status.reset() && !self.state.regs.get::<Power>().reset() {
+            warn!("{:?} unexpected device reset", cmd);
+            self.restore_configuration()?;
+            return Err(Ads126xError::DeviceReset);
+        }
+
+        // Reference and gain active during conversion
+        let (vref_uv, gain) = if let Some(Command::RData2) = cmd {
+            let adc2cfg = self.state.regs.get::<Adc2Cfg>();
+            (self.state.vref2_uv, adc2cfg.pga_gain())
+        } else {
+            let mode2 = self.state.regs.get::<Mode2>();
+            (self.state.vref1_uv, mode2.pga_gain())
+        };
+
+        let data = Data::new(status, code, vref_uv, gain);
+        log_in!(DataFrame, "{:?} frame {:?} {:?}", cmd, frame, data);
+        Ok(data)
+    }
+
+    /// Reset device by sending the RESET command
+    ///
+    /// Waits the minimum time required before communicating with the device again and clears
+    /// the [Power::reset] flag so that a later unexpected reset can be detected.
+    pub fn soft_reset<DELAY>(&mut self, mut delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        self.send_command(Command::Reset)?;
+        delay.delay_us(RESET_DELAY_US);
+        self.reset_done()
+    }
+
+    /// Clear the [Power::reset] flag
+    ///
+    /// Arms reset detection: a reset reported afterwards is recovered from, see
+    /// [Self::restore_configuration].
+    pub fn clear_reset_flag(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let mut power = self.read_reg::<Power>()?;
+        power.set_reset(false);
+        self.write_reg(power)
+    }
+
+    /// Registers are back at their defaults after a reset, both ADCs use the internal reference
+    fn reset_done(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.state.regs = Default::default();
+        self.state.vref1_uv = INTERNAL_REF_UV;
+        self.state.vref2_uv = INTERNAL_REF_UV;
+        self.state.standby = None;
+        self.state.running = [false; 2];
+        self.clear_reset_flag()
     }
 }
 
-impl<SPI, CS, DRDY, RST, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X>
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
 where
     SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
     CS: OutputPin<Error = IoErr>,
-    DRDY: InputPin<Error = IoErr>,
     RST: OutputPin<Error = IoErr>,
     X: private::Sealed,
+{
+    /// Reset device by pulsing RST low
+    ///
+    /// Waits the minimum time required before communicating with the device again and clears
+    /// the [Power::reset] flag so that a later unexpected reset can be detected.
+    ///
+    /// **NOTE: ** Only available when RST implements embedded_hal [OutputPin], use [Self::soft_reset] otherwise.
+    pub fn reset<DELAY>(&mut self, mut delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayMs<u32>,
+    {
+        self.rst.set_low().map_err(Ads126xError::IoErr)?;
+        delay.delay_ms(RESET_PULSE_MS);
+        self.rst.set_high().map_err(Ads126xError::IoErr)?;
+        delay.delay_ms(RESET_DELAY_US.div_ceil(1_000));
+        self.reset_done()
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    START: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Drive START high, starting ADC1 conversions
+    ///
+    /// **NOTE: ** Only available when START implements embedded_hal [OutputPin].
+    pub fn start_high(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.start.set_high().map_err(Ads126xError::IoErr)
+    }
+
+    /// Drive START low, conversions stop after the ongoing conversion completes
+    ///
+    /// **NOTE: ** Only available when START implements embedded_hal [OutputPin].
+    pub fn start_low(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.start.set_low().map_err(Ads126xError::IoErr)
+    }
+
+    /// Pulse START to trigger a single ADC1 conversion (pulse conversion mode, see [Mode0::run_mode](crate::registers::Mode0::run_mode))
+    ///
+    /// **NOTE: ** Only available when START implements embedded_hal [OutputPin].
+    pub fn pulse_start<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        pulse_start(&mut self.start, &mut delay).map_err(Ads126xError::IoErr)
+    }
+}
+
+/// Pulse a START pin high for the minimum pulse width
+pub(crate) fn pulse_start<START, DELAY>(
+    start: &mut START,
+    delay: &mut DELAY,
+) -> Result<(), START::Error>
+where
+    START: OutputPin,
+    DELAY: DelayUs<u32>,
+{
+    start.set_high()?;
+    delay.delay_us(START_PULSE_US);
+    start.set_low()
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
 {
     /// Returns true if DRDY' is active i.e. low.
     ///
     /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
     pub fn drdy(&mut self) -> Result<bool, Ads126xError<SpiErr, IoErr>> {
-        self.drdy.is_low().map_err(|e| Ads126xError::IoErr(e))
+        self.drdy.is_low().map_err(Ads126xError::IoErr)
     }
 
     /// Busy-sleep loop waiting for DRDY to go low. Probably want to use something else to avoid a lockup if DRDY never goes low.
@@ -248,11 +705,10 @@ where
     }
 }
 
-impl<SPI, CS, DRDY, RST, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X>
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
 where
     SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
     CS: OutputPin<Error = IoErr>,
-    RST: OutputPin<Error = IoErr>,
     X: private::Sealed,
 {
     /// Send START1 command to start ADC1 conversion
@@ -265,6 +721,28 @@ where
         self.send_command(Command::Stop1)
     }
 
+    /// Send SFOCAL1 command to start ADC1 self offset calibration
+    ///
+    /// ADC1 must be converting, completion is signaled by DRDY. The result is stored in OFCAL,
+    /// see [Self::read_calibration].
+    pub fn sfocal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Sfocal1)
+    }
+
+    /// Send SYOCAL1 command to start ADC1 system offset calibration, the input must be shorted externally
+    ///
+    /// ADC1 must be converting, completion is signaled by DRDY. The result is stored in OFCAL.
+    pub fn syocal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Syocal1)
+    }
+
+    /// Send SYGCAL1 command to start ADC1 system gain calibration, a full-scale voltage must be applied
+    ///
+    /// ADC1 must be converting, completion is signaled by DRDY. The result is stored in FSCAL.
+    pub fn sygcal1(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Sygcal1)
+    }
+
     /// Read data from ADC1
     pub fn read_adc1(&mut self) -> Result<Data, Ads126xError<SpiErr, IoErr>> {
         self.read_data(Some(Command::RData1))
@@ -278,28 +756,238 @@ where
     }
 
     pub fn read_offs_cal1(&mut self) -> Result<i32, Ads126xError<SpiErr, IoErr>> {
-        let mut data = [Command::RReg.reg(OFCAL0), 0x02, 0x00, 0x00, 0x00];
+        let mut data = [0; 3];
+        self.read_regs(OFCAL0, &mut data)?;
 
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi
-            .transfer(&mut data)
-            .map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
-
-        let value = (data[2] as i32) | (data[3] as i32) << 8 | (data[4] as i32) << 16;
+        // Sign extend 24-bit value
+        let value = i32::from_le_bytes([0, data[0], data[1], data[2]]) >> 8;
         Ok(value)
     }
 
     pub fn read_fs_cal1(&mut self) -> Result<u32, Ads126xError<SpiErr, IoErr>> {
-        let mut data = [Command::RReg.reg(FSCAL0), 0x02, 0x00, 0x00, 0x00];
-
-        self.cs.set_low().map_err(|e| Ads126xError::IoErr(e))?;
-        self.spi
-            .transfer(&mut data)
-            .map_err(|e| Ads126xError::SpiErr(e))?;
-        self.cs.set_high().map_err(|e| Ads126xError::IoErr(e))?;
+        let mut data = [0; 3];
+        self.read_regs(FSCAL0, &mut data)?;
 
-        let value = (data[2] as u32) | (data[3] as u32) << 8 | (data[4] as u32) << 16;
+        let value = u32::from_le_bytes([data[0], data[1], data[2], 0]);
         Ok(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, convert::Infallible};
+
+    use embedded_hal::{
+        blocking::{
+            delay::DelayUs,
+            spi::{Transfer, Write},
+        },
+        digital::v2::OutputPin,
+    };
+
+    use super::{Ads126xError, AnyAds126x, CrcError, Data, Reference, RESET_DELAY_US};
+    use crate::{
+        registers::{IdDev, Interface, Mode2, Mode2Dr, Mode2Gain, Power, RegisterMap, POWER},
+        sim::SimAds126x,
+        Ads1262, Ads1263, DrdyNoConnection, RstNoConnection,
+    };
+
+    /// SPI bus answering every transfer with a fixed response
+    struct FixedSpi(&'static [u8]);
+
+    impl Transfer<u8> for FixedSpi {
+        type Error = Infallible;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+            words.copy_from_slice(&self.0[..words.len()]);
+            Ok(words)
+        }
+    }
+
+    impl Write<u8> for FixedSpi {
+        type Error = Infallible;
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Pin(bool);
+
+    impl OutputPin for Pin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0 = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_crc_error_releases_cs() {
+        // RDATA1 echo, status, data and a bad checksum
+        let spi = FixedSpi(&[0xFF, 0x40, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        let mut adc = Ads1263::new(spi, Pin(true), DrdyNoConnection, Pin(true));
+        adc.state.regs.set(Interface(0x05));
+
+        let err = adc.read_adc1().unwrap_err();
+        assert!(adc.cs.0, "CS left asserted");
+        match err {
+            Ads126xError::Crc(e) => {
+                assert_eq!(e, CrcError::new(0x9C, 0x00, &[0x40, 0, 0, 0, 1, 0]));
+                assert_eq!(e.frame(), &[0x40, 0, 0, 0, 1, 0]);
+            }
+            e => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn test_register_range() {
+        let spi = FixedSpi(&[0xFF; 4]);
+        let mut adc = Ads1263::new(spi, Pin(true), DrdyNoConnection, RstNoConnection);
+        for addr in [0x1B, 0xFF] {
+            assert!(matches!(
+                adc.write_reg_at(addr, 0),
+                Err(Ads126xError::InvalidRegister(a)) if a == addr
+            ));
+            assert!(matches!(
+                adc.read_reg_at(addr),
+                Err(Ads126xError::InvalidRegister(a)) if a == addr
+            ));
+        }
+        adc.write_reg_at(0x1A, 0x55).unwrap();
+        assert_eq!(adc.registers().0[0x1A], 0x55);
+    }
+
+    /// Delay adding up the requested time
+    struct TotalDelay<'a>(&'a Cell<u32>);
+
+    impl DelayUs<u32> for TotalDelay<'_> {
+        fn delay_us(&mut self, us: u32) {
+            self.0.set(self.0.get() + us);
+        }
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let sim = SimAds126x::ads1263();
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        assert!(sim.registers().get::<Power>().reset());
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false))
+            .unwrap();
+        adc.start1().unwrap();
+
+        let waited = Cell::new(0);
+        adc.soft_reset(TotalDelay(&waited)).unwrap();
+        assert_eq!(waited.get(), RESET_DELAY_US);
+        assert_eq!(sim.registers().get::<Mode2>().0, 0x04);
+        assert!(!sim.adc1_running());
+
+        // The flag is cleared on the device and in the cache, everything else is at its default
+        assert!(!sim.registers().get::<Power>().reset());
+        assert!(!adc.registers().get::<Power>().reset());
+        let mut defaults = RegisterMap::default();
+        defaults.0[0] = sim.registers().0[0];
+        defaults.0[POWER as usize] = adc.registers().0[POWER as usize];
+        assert_eq!(sim.registers(), defaults);
+        assert_eq!(adc.registers().0[1..], defaults.0[1..]);
+    }
+
+    #[test]
+    fn test_probe() {
+        let id = |id: &'static [u8]| (FixedSpi(id), Pin(true), DrdyNoConnection, RstNoConnection);
+
+        let (spi, cs, drdy, rst) = id(&[0xFF, 0xFF, 0x23]);
+        assert!(Ads1263::probe(spi, cs, drdy, rst).is_ok());
+
+        let (spi, cs, drdy, rst) = id(&[0xFF, 0xFF, 0x23]);
+        assert!(matches!(
+            Ads1262::probe(spi, cs, drdy, rst),
+            Err(Ads126xError::WrongDevice(0x23))
+        ));
+
+        for dead in [&[0x00; 3], &[0xFF; 3]] {
+            let (spi, cs, drdy, rst) = id(dead);
+            assert!(matches!(
+                AnyAds126x::probe(spi, cs, drdy, rst),
+                Err(Ads126xError::NoDevice)
+            ));
+        }
+
+        for unsupported in [&[0xFF, 0xFF, 0x24], &[0xFF, 0xFF, 0x3F]] {
+            let (spi, cs, drdy, rst) = id(unsupported);
+            assert!(matches!(
+                Ads1263::probe(spi, cs, drdy, rst),
+                Err(Ads126xError::UnsupportedRevision(b)) if b == unsupported[2]
+            ));
+        }
+
+        let (spi, cs, drdy, rst) = id(&[0xFF, 0xFF, 0x03]);
+        let mut adc = AnyAds126x::probe(spi, cs, drdy, rst).unwrap();
+        assert_eq!(adc.device(), IdDev::Ads1262);
+        assert!(matches!(adc.read_adc2(), Err(Ads126xError::Unsupported)));
+    }
+
+    #[test]
+    fn test_data_reference_and_gain() {
+        // RDATA1 echo, data = 2^30
+        let spi = FixedSpi(&[0xFF, 0x40, 0x00, 0x00, 0x00]);
+        let mut adc = Ads1263::new(spi, Pin(true), DrdyNoConnection, RstNoConnection);
+        adc.state.regs.set(Interface(0x00));
+
+        let data = adc.read_adc1().unwrap();
+        assert_eq!((data.vref_uv, data.gain), (2_500_000, 1));
+        assert_eq!(data.to_microvolts(), 1_250_000);
+
+        adc.set_reference(Reference::supply(5_000_000)).unwrap();
+        adc.write_reg(Mode2::with(Mode2Dr::Sps20, Mode2Gain::Gain4, false))
+            .unwrap();
+        let data = adc.read_adc1().unwrap();
+        assert_eq!((data.vref_uv, data.gain), (5_000_000, 4));
+        assert_eq!(data.to_microvolts(), 625_000);
+    }
+
+    #[test]
+    fn test_fixed_point_conversion() {
+        let data = |code, vref_uv, gain| Data {
+            code,
+            vref_uv,
+            gain,
+            ..Default::default()
+        };
+
+        // Full scale
+        let d = data(i32::MAX, 2_500_000, 1);
+        assert_eq!(d.to_microvolts(), 2_500_000);
+        assert_eq!(d.to_nanovolts(), 2_499_999_999);
+        let d = data(i32::MIN, 2_500_000, 32);
+        assert_eq!(d.to_microvolts(), -78_125);
+        assert_eq!(d.to_nanovolts(), -78_125_000);
+
+        // 1 LSB at 5 V reference = 2.328 nV, rounds half away from zero
+        assert_eq!(data(1, 5_000_000, 1).to_nanovolts(), 2);
+        assert_eq!(data(-1, 5_000_000, 1).to_nanovolts(), -2);
+        assert_eq!(data(0x8000, 4_194_304, 1).to_microvolts(), 64);
+        assert_eq!(data(-0x3000, 1 << 24, 1).to_microvolts(), -96);
+    }
+
+    #[test]
+    fn test_temperature() {
+        let data = |uv: i64| Data {
+            code: (((uv << 31) + 1_250_000) / 2_500_000) as i32,
+            vref_uv: 2_500_000,
+            gain: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(data(122_400).to_millicelsius(), 25_000);
+        assert_eq!(data(122_400 + 4_200).to_millicelsius(), 35_000);
+        assert_eq!(data(122_400 - 10_500).to_millicelsius(), 0);
+    }
+}
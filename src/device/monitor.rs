@@ -0,0 +1,256 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::registers::{Adc2Mux, InpMuxMuxx, Interface};
+
+use super::{ads1263::_Ads1263, Ads126x, Ads126xError, Data};
+
+/// Diagnostic channel converted by ADC2 while ADC1 measures, see [Monitor]
+///
+/// ADC2 should run at gain 1 with the internal reference for the supply and temperature channels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Health {
+    /// Analog supply AVDD − AVSS in uV
+    AnalogSupply,
+    /// Digital supply DVDD − DGND in uV
+    DigitalSupply,
+    /// Internal temperature sensor in milli degrees Celsius
+    Temperature,
+    /// Voltage between two inputs in uV, e.g. an external reference or a sensor excitation
+    Input(InpMuxMuxx, InpMuxMuxx),
+}
+
+impl Health {
+    /// ADC2 input selection of the channel
+    pub fn mux(&self) -> Adc2Mux {
+        match *self {
+            Health::AnalogSupply => {
+                Adc2Mux::differential(InpMuxMuxx::AnalogPower, InpMuxMuxx::AnalogPower)
+            }
+            Health::DigitalSupply => {
+                Adc2Mux::differential(InpMuxMuxx::DigitalPower, InpMuxMuxx::DigitalPower)
+            }
+            Health::Temperature => {
+                Adc2Mux::differential(InpMuxMuxx::Temperature, InpMuxMuxx::Temperature)
+            }
+            Health::Input(inp, inn) => Adc2Mux::differential(inp, inn),
+        }
+    }
+
+    /// Value of an ADC2 reading of the channel, in the unit of the channel
+    pub fn value(&self, data: &Data) -> i64 {
+        match self {
+            // The supplies are divided by 4 at the input
+            Health::AnalogSupply | Health::DigitalSupply => data.to_microvolts() * 4,
+            Health::Temperature => data.to_millicelsius().into(),
+            Health::Input(..) => data.to_microvolts(),
+        }
+    }
+}
+
+/// Latest ADC2 reading of a [Health] channel
+#[derive(Debug, Copy, Clone)]
+pub struct HealthReading {
+    pub channel: Health,
+    /// See [Health::value]
+    pub value: i64,
+    pub data: Data,
+}
+
+/// ADC1 conversion annotated with the latest reading of each [Health] channel
+#[derive(Debug, Copy, Clone)]
+pub struct Monitored<const N: usize> {
+    pub data: Data,
+    /// In the order of the channels of the [Monitor], `None` until a channel was converted
+    pub health: [Option<HealthReading>; N],
+}
+
+/// Coordinates ADC1 measurements with ADC2 cycling through `N` [Health] channels
+///
+/// Started with [Ads126x::start_monitor] and read with [Ads126x::read_monitored]. Both ADCs are
+/// served from DRDY: every ADC1 conversion is read, and ADC2 is read whenever the status byte
+/// reports new ADC2 data, after which ADC2 moves on to the next channel.
+#[derive(Debug, Clone)]
+pub struct Monitor<const N: usize> {
+    channels: [Health; N],
+    health: [Option<HealthReading>; N],
+    current: usize,
+    /// ADC2 conversions discarded after each channel change
+    ///
+    /// Writing ADC2MUX restarts the conversion, the default of one also discards a conversion
+    /// that completed before the change took effect.
+    pub settle: u8,
+    skip: u8,
+}
+
+impl<const N: usize> Monitor<N> {
+    pub fn new(channels: [Health; N]) -> Self {
+        Self {
+            channels,
+            health: [None; N],
+            current: 0,
+            settle: 1,
+            skip: 0,
+        }
+    }
+
+    /// Latest readings, in the order of the channels
+    pub fn health(&self) -> &[Option<HealthReading>; N] {
+        &self.health
+    }
+
+    /// Latest reading of `channel` if it is monitored
+    pub fn reading(&self, channel: Health) -> Option<HealthReading> {
+        self.health
+            .iter()
+            .flatten()
+            .find(|r| r.channel == channel)
+            .copied()
+    }
+
+    /// Record an ADC2 reading, returns the channel to select next if the channel changes
+    fn record(&mut self, data: Data) -> Option<Adc2Mux> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+        let channel = self.channels[self.current];
+        self.health[self.current] = Some(HealthReading {
+            channel,
+            value: channel.value(&data),
+            data,
+        });
+        if N < 2 {
+            return None;
+        }
+        self.current = (self.current + 1) % N;
+        self.skip = self.settle;
+        Some(self.channels[self.current].mux())
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr>
+    Ads126x<SPI, CS, DRDY, RST, _Ads1263, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+{
+    /// Select the first channel of `monitor` on ADC2 and start both ADCs
+    ///
+    /// ADC1 measures with its current configuration. The status byte must be enabled in
+    /// [Interface] to see new ADC2 data.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn start_monitor<const N: usize>(
+        &mut self,
+        monitor: &mut Monitor<N>,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        monitor.health = [None; N];
+        monitor.current = 0;
+        monitor.skip = monitor.settle;
+        if let Some(channel) = monitor.channels.first() {
+            self.write_reg(channel.mux())?;
+            self.start2()?;
+        }
+        self.start1()
+    }
+
+    /// Read the next ADC1 conversion, polling DRDY every `us` microseconds, and serve ADC2
+    ///
+    /// Fails with [Ads126xError::Unsupported] if the status byte is disabled in [Interface].
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn read_monitored<DELAY, const N: usize>(
+        &mut self,
+        monitor: &mut Monitor<N>,
+        mut delay: DELAY,
+        us: u32,
+    ) -> Result<Monitored<N>, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        if !self.state.regs.get::<Interface>().status() {
+            return Err(Ads126xError::Unsupported);
+        }
+        loop {
+            while !self.drdy()? {
+                delay.delay_us(us);
+            }
+            let data = self.read_adc1()?;
+            if data.status.adc2() && N > 0 {
+                let data2 = self.read_adc2()?;
+                if let Some(mux) = monitor.record(data2) {
+                    self.write_reg(mux)?;
+                }
+            }
+            if data.status.adc1() {
+                return Ok(Monitored {
+                    data,
+                    health: monitor.health,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Health, Monitor};
+    use crate::{
+        device::Ads126xError,
+        registers::{Adc2Cfg, InpMux, InpMuxMuxx, Interface},
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_monitor() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 1_000_000);
+        sim.set_input(InpMuxMuxx::Ain8, 1_200_000);
+        sim.set_supplies(5_000_000, 3_300_000);
+        sim.set_temperature(30_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.set_intref(true).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+
+        let mut monitor = Monitor::new([
+            Health::AnalogSupply,
+            Health::DigitalSupply,
+            Health::Temperature,
+            Health::Input(InpMuxMuxx::Ain8, InpMuxMuxx::AinCom),
+        ]);
+        monitor.settle = 0;
+        adc.start_monitor(&mut monitor).unwrap();
+        let first = adc.read_monitored(&mut monitor, NoDelay, 10).unwrap();
+        assert_eq!(first.data.to_microvolts(), 1_000_000);
+        assert_eq!(first.health[0].unwrap().value, 5_000_000);
+        assert!(first.health[1].is_none());
+        for _ in 0..3 {
+            adc.read_monitored(&mut monitor, NoDelay, 10).unwrap();
+        }
+
+        let values = monitor.health().map(|r| r.unwrap().value);
+        assert_eq!(values[..2], [5_000_000, 3_300_000]);
+        assert!((values[2] - 30_000).abs() < 10);
+        assert_eq!(values[3], 1_200_000);
+        assert_eq!(
+            monitor.reading(Health::Temperature).unwrap().data.gain,
+            adc.registers().get::<Adc2Cfg>().pga_gain()
+        );
+        assert!(sim.adc1_running() && sim.adc2_running());
+
+        adc.write_reg(Interface(0)).unwrap();
+        assert!(matches!(
+            adc.read_monitored(&mut monitor, NoDelay, 10),
+            Err(Ads126xError::Unsupported)
+        ));
+    }
+}
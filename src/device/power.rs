@@ -0,0 +1,370 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    commands::Command,
+    registers::{IdDev, Mode2Dr, Power},
+    PwdnNoConnection,
+};
+
+use super::{private, Ads126x, Ads126xError, Data};
+
+/// Delay after PWDN is released until the device can be communicated with, 2^16 tCLK (~8.9 ms)
+const WAKEUP_DELAY_US: u32 = 9_000;
+/// Internal reference settling time with the recommended 10 uF capacitor on REFOUT
+const INTREF_SETTLE_US: u32 = 50_000;
+
+/// A pin that may or may not be connected, implemented for all [OutputPin]s and [PwdnNoConnection].
+pub trait OptionalPin<E> {
+    /// Drive pin high or low, returns false if no pin is connected
+    fn set_state(&mut self, high: bool) -> Result<bool, E>;
+}
+
+impl<E> OptionalPin<E> for PwdnNoConnection {
+    fn set_state(&mut self, _high: bool) -> Result<bool, E> {
+        Ok(false)
+    }
+}
+
+impl<P> OptionalPin<P::Error> for P
+where
+    P: OutputPin,
+{
+    fn set_state(&mut self, high: bool) -> Result<bool, P::Error> {
+        if high {
+            self.set_high()?;
+        } else {
+            self.set_low()?;
+        }
+        Ok(true)
+    }
+}
+
+/// Estimated supply currents (AVDD + DVDD) in uA used by [DutyCycle].
+///
+/// Defaults are rough typical figures, measure the actual board for accurate estimates.
+#[derive(Debug, Copy, Clone)]
+pub struct SupplyCurrent {
+    /// ADC1 converting, internal reference off
+    pub active_ua: u32,
+    /// Additional current drawn by the internal reference
+    pub intref_ua: u32,
+    /// ADCs stopped, internal reference and VBIAS off
+    pub standby_ua: u32,
+    /// PWDN held low
+    pub power_down_ua: u32,
+}
+
+impl Default for SupplyCurrent {
+    fn default() -> Self {
+        Self {
+            active_ua: 5_500,
+            intref_ua: 250,
+            standby_ua: 600,
+            power_down_ua: 5,
+        }
+    }
+}
+
+/// Duty-cycled sampling where the device sleeps between bursts of readings.
+///
+/// Use [Ads126x::sample_duty_cycled] to take a burst of readings.
+#[derive(Debug, Copy, Clone)]
+pub struct DutyCycle {
+    /// Time between the start of each burst in ms
+    pub period_ms: u32,
+    /// Conversion period of the configured data rate in us, see [Mode2Dr::period_us]
+    pub conversion_us: u32,
+    /// Internal reference is enabled while awake
+    pub intref: bool,
+    /// PWDN pin is connected
+    pub pwdn: bool,
+    /// Supply current estimates
+    pub current: SupplyCurrent,
+}
+
+impl DutyCycle {
+    pub fn new(period_ms: u32, dr: Mode2Dr, intref: bool, pwdn: bool) -> Self {
+        Self {
+            period_ms,
+            conversion_us: dr.period_us(),
+            intref,
+            pwdn,
+            current: Default::default(),
+        }
+    }
+
+    /// Estimated time awake in us for a burst of `readings` readings
+    pub fn active_us(&self, readings: u32) -> u64 {
+        let mut t = u64::from(self.conversion_us) * u64::from(readings);
+        if self.pwdn {
+            t += u64::from(WAKEUP_DELAY_US);
+        }
+        if self.intref {
+            t += u64::from(INTREF_SETTLE_US);
+        }
+        t
+    }
+
+    /// Estimated average supply current in uA when taking `readings` readings every period
+    pub fn average_current_ua(&self, readings: u32) -> u32 {
+        let period = u64::from(self.period_ms) * 1000;
+        let active = self.active_us(readings).min(period);
+
+        let mut i_active = u64::from(self.current.active_ua);
+        if self.intref {
+            i_active += u64::from(self.current.intref_ua);
+        }
+        let i_sleep = if self.pwdn {
+            self.current.power_down_ua
+        } else {
+            self.current.standby_ua
+        };
+
+        if period == 0 {
+            return i_active as u32;
+        }
+        let charge = i_active * active + u64::from(i_sleep) * (period - active);
+        (charge / period) as u32
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    PWDN: OptionalPin<IoErr>,
+    X: private::Sealed,
+{
+    /// Stop both ADCs, turn off the internal reference and VBIAS and pull PWDN low (if connected).
+    ///
+    /// The [Power] register is restored by [Self::power_up].
+    pub fn power_down(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        self.send_command(Command::Stop1)?;
+        if X::DEVICE == IdDev::Ads1263 {
+            self.send_command(Command::Stop2)?;
+        }
+
+        let power = self.read_reg::<Power>()?;
+        let mut off = power;
+        off.set_intref(false);
+        off.set_vbias(false);
+        self.write_reg(off)?;
+        if self.state.standby.is_none() {
+            self.state.standby = Some(power);
+        }
+
+        self.pwdn.set_state(false).map_err(Ads126xError::IoErr)?;
+        Ok(())
+    }
+
+    /// Release PWDN (if connected) and restore the [Power] register saved by [Self::power_down].
+    ///
+    /// Waits for the device to wake up and for the internal reference to settle if enabled.
+    /// ADCs must be restarted afterwards.
+    pub fn power_up<DELAY>(&mut self, mut delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        self.power_up_inner(&mut delay)
+    }
+
+    fn power_up_inner<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        if self.pwdn.set_state(true).map_err(Ads126xError::IoErr)? {
+            delay.delay_us(WAKEUP_DELAY_US);
+        }
+
+        if let Some(power) = self.state.standby.take() {
+            self.write_reg(power)?;
+            if power.intref() {
+                delay.delay_us(INTREF_SETTLE_US);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Turn internal reference on or off
+    ///
+    /// [Power] is read from the device, so a reset or an earlier change is not undone.
+    pub fn set_intref(&mut self, on: bool) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let mut power = self.read_reg::<Power>()?;
+        power.set_intref(on);
+        self.write_reg(power)
+    }
+
+    /// Turn VBIAS (AINCOM level shift) on or off
+    pub fn set_vbias(&mut self, on: bool) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let mut power = self.read_reg::<Power>()?;
+        power.set_vbias(on);
+        self.write_reg(power)
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    PWDN: OptionalPin<IoErr>,
+    X: private::Sealed,
+{
+    /// Wake device, fill `readings` with ADC1 conversions polling DRDY every `us` microseconds and power it down again.
+    ///
+    /// ADC1 should be configured for continuous conversion mode. Use [DutyCycle] to estimate the resulting
+    /// average supply current. The device is powered down again when a reading fails.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn sample_duty_cycled<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+        readings: &mut [Data],
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let res = self
+            .power_up_inner(&mut delay)
+            .and_then(|_| self.start1())
+            .and_then(|_| {
+                for data in readings.iter_mut() {
+                    while !self.drdy()? {
+                        delay.delay_us(us);
+                    }
+                    *data = self.read_adc1()?;
+                }
+                Ok(())
+            });
+
+        // Power down even if the burst failed
+        let restore = self.power_down();
+        res.and(restore)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, convert::Infallible};
+
+    use embedded_hal::digital::v2::OutputPin;
+
+    use super::DutyCycle;
+    use crate::{
+        device::{Ads126xError, Data},
+        registers::{InpMux, InpMuxMuxx, Mode2Dr, Power},
+        sim::{Faults, NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    /// PWDN pin recording its level, the simulated device has no PWDN input
+    struct Pwdn<'a>(&'a Cell<bool>);
+
+    impl OutputPin for Pwdn<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_average_current() {
+        // 10 readings at 100 SPS once per second, 109 ms awake
+        let dc = DutyCycle::new(1000, Mode2Dr::Sps100, false, true);
+        assert_eq!(dc.active_us(10), 109_000);
+        assert_eq!(dc.average_current_ua(10), (5_500 * 109 + 5 * 891) / 1000);
+
+        // Always awake
+        assert_eq!(dc.average_current_ua(1000), 5_500);
+    }
+
+    #[test]
+    fn test_power_down_up() {
+        let sim = SimAds126x::ads1263();
+        let pwdn = Cell::new(true);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst())
+            .unwrap()
+            .with_pwdn(Pwdn(&pwdn));
+        adc.set_intref(true).unwrap();
+        adc.set_vbias(true).unwrap();
+        assert!(sim.registers().get::<Power>().vbias());
+        adc.start1().unwrap();
+        adc.start2().unwrap();
+
+        adc.power_down().unwrap();
+        let power = sim.registers().get::<Power>();
+        assert!(!power.intref() && !power.vbias());
+        assert!(!sim.adc1_running() && !sim.adc2_running());
+        assert!(!pwdn.get());
+
+        // A second power down keeps the saved configuration
+        adc.power_down().unwrap();
+        adc.power_up(NoDelay).unwrap();
+        let power = sim.registers().get::<Power>();
+        assert!(power.intref() && power.vbias());
+        assert!(pwdn.get());
+
+        adc.set_vbias(false).unwrap();
+        assert!(!sim.registers().get::<Power>().vbias());
+    }
+
+    #[test]
+    fn test_sample_duty_cycled() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let pwdn = Cell::new(true);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst())
+            .unwrap()
+            .with_pwdn(Pwdn(&pwdn));
+        adc.set_intref(true).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.power_down().unwrap();
+
+        let mut readings = [Data::default(); 3];
+        adc.sample_duty_cycled(NoDelay, 10, &mut readings).unwrap();
+        assert!(readings.iter().all(|d| d.to_microvolts() == 100_000));
+        assert!(!pwdn.get() && !sim.adc1_running());
+        assert!(!sim.registers().get::<Power>().intref());
+
+        // A failed reading still powers the device down
+        sim.set_faults(Faults {
+            corrupt_crc: true,
+            ..Default::default()
+        });
+        assert!(matches!(
+            adc.sample_duty_cycled(NoDelay, 10, &mut readings),
+            Err(Ads126xError::Crc(_))
+        ));
+        assert!(!pwdn.get() && !sim.adc1_running());
+        assert!(!sim.registers().get::<Power>().intref());
+
+        sim.set_faults(Faults::default());
+        adc.power_up(NoDelay).unwrap();
+        assert!(sim.registers().get::<Power>().intref());
+    }
+}
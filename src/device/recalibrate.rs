@@ -0,0 +1,284 @@
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    commands::Command,
+    registers::{IdDev, InpMux, Mode2, Mode2Gain, ADC2OFC0},
+};
+
+use super::{private, Ads126x, Ads126xError};
+
+/// Reason for an offset recalibration
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecalTrigger {
+    /// No recalibration was done by the schedule yet
+    First,
+    /// The interval elapsed since the last recalibration
+    Interval,
+    /// The die temperature changed by more than the threshold since the last recalibration
+    Temperature,
+}
+
+/// Offset recalibration performed by [Ads126x::recalibrate_if_due]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecalEvent {
+    pub trigger: RecalTrigger,
+    /// Die temperature at the recalibration in milli degrees Celsius
+    pub temperature_mc: i32,
+    /// ADC1 OFCAL before and after
+    pub ofcal1: (i32, i32),
+    /// ADC2 ADC2OFC before and after, `None` if ADC2 was not recalibrated
+    pub ofcal2: Option<(i16, i16)>,
+}
+
+/// Schedule of self offset recalibrations (SFOCAL1/SFOCAL2) tracking time and die temperature
+///
+/// Time is supplied by the caller as a free running millisecond counter that may wrap.
+#[derive(Debug, Clone)]
+pub struct Recalibration {
+    /// Maximum time between recalibrations in ms
+    pub interval_ms: u32,
+    /// Change of the die temperature that triggers a recalibration in milli degrees Celsius
+    pub drift_mc: u32,
+    /// Also recalibrate ADC2 on a ADS1263 while it is converting
+    pub adc2: bool,
+    /// Time and temperature of the last recalibration
+    last: Option<(u32, i32)>,
+}
+
+impl Recalibration {
+    pub fn new(interval_ms: u32, drift_mc: u32) -> Self {
+        Self {
+            interval_ms,
+            drift_mc,
+            adc2: true,
+            last: None,
+        }
+    }
+
+    /// Whether a recalibration is due at `now_ms` with the die at `temperature_mc`
+    pub fn due(&self, now_ms: u32, temperature_mc: i32) -> Option<RecalTrigger> {
+        let Some((at_ms, at_mc)) = self.last else {
+            return Some(RecalTrigger::First);
+        };
+        if temperature_mc.abs_diff(at_mc) > self.drift_mc {
+            Some(RecalTrigger::Temperature)
+        } else if now_ms.wrapping_sub(at_ms) >= self.interval_ms {
+            Some(RecalTrigger::Interval)
+        } else {
+            None
+        }
+    }
+
+    /// Die temperature at the last recalibration in milli degrees Celsius
+    pub fn last_temperature_mc(&self) -> Option<i32> {
+        self.last.map(|(_, mc)| mc)
+    }
+
+    /// Recalibrate on the next check, e.g. after a change of gain or data rate
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Measure the die temperature and run the self offset calibration if `schedule` is due,
+    /// polling every `us` microseconds
+    ///
+    /// Call between acquisitions: the temperature is converted on ADC1 (two conversions at gain 1)
+    /// and MODE2, INPMUX and the ADC1 run state are restored afterwards, which restarts the
+    /// conversion so the next data read belongs to the user configuration. ADC1 is calibrated at
+    /// the configured gain and data rate. ADC2 is recalibrated if it is converting, completion is
+    /// detected with the status byte enabled in [Interface](crate::registers::Interface).
+    ///
+    /// The new calibration is stored in [Self::registers], so it survives
+    /// [Self::restore_configuration].
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn recalibrate_if_due<DELAY>(
+        &mut self,
+        schedule: &mut Recalibration,
+        mut delay: DELAY,
+        us: u32,
+        now_ms: u32,
+    ) -> Result<Option<RecalEvent>, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let mode2 = self.state.regs.get::<Mode2>().0;
+        let inpmux = self.state.regs.get::<InpMux>().0;
+        let running = self.state.running[0];
+
+        let res = self.run_recalibration(schedule, &mut delay, us, now_ms, mode2);
+        let restore = self
+            .write_reg(Mode2(mode2))
+            .and_then(|_| self.write_reg(InpMux(inpmux)))
+            .and_then(|_| if running { Ok(()) } else { self.stop1() });
+        res.and_then(|r| restore.map(|_| r))
+    }
+
+    fn run_recalibration<DELAY>(
+        &mut self,
+        schedule: &mut Recalibration,
+        delay: &mut DELAY,
+        us: u32,
+        now_ms: u32,
+        mode2: u8,
+    ) -> Result<Option<RecalEvent>, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let mut gain1 = Mode2(mode2);
+        gain1.set_gain(Mode2Gain::None as u8);
+        self.write_reg(gain1)?;
+        self.start1()?;
+        let temperature_mc = self
+            .convert1(delay, us, InpMux::temperature())?
+            .to_millicelsius();
+        let Some(trigger) = schedule.due(now_ms, temperature_mc) else {
+            return Ok(None);
+        };
+
+        let before = self.read_offs_cal1()?;
+        self.write_reg(Mode2(mode2))?;
+        self.send_command(Command::Sfocal1)?;
+        while !self.drdy()? {
+            delay.delay_us(us);
+        }
+
+        let adc2 = X::DEVICE == IdDev::Ads1263 && schedule.adc2 && self.state.running[1];
+        let before2 = if adc2 {
+            let mut buf = [0; 2];
+            self.read_regs(ADC2OFC0, &mut buf)?;
+            // Clear a pending new data flag so that completion is not detected early
+            self.read_data(Some(Command::RData2))?;
+            self.send_command(Command::Sfocal2)?;
+            while !self.read_data(Some(Command::RData2))?.status.adc2() {
+                delay.delay_us(us);
+            }
+            Some(i16::from_le_bytes(buf))
+        } else {
+            None
+        };
+
+        let cal = self.read_calibration()?;
+        info!(
+            "{:?} SFOCAL1 at {} mC, OFCAL {} -> {}",
+            trigger, temperature_mc, before, cal.ofcal1
+        );
+        if let Some(before2) = before2 {
+            info!("SFOCAL2 ADC2OFC {} -> {}", before2, cal.ofcal2);
+        }
+        schedule.last = Some((now_ms, temperature_mc));
+        Ok(Some(RecalEvent {
+            trigger,
+            temperature_mc,
+            ofcal1: (before, cal.ofcal1),
+            ofcal2: before2.map(|b| (b, cal.ofcal2)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecalTrigger, Recalibration};
+    use crate::{
+        registers::{
+            Adc2Cfg, Adc2CfgDr, Adc2CfgGain, Adc2CfgRef, Adc2Mux, InpMux, InpMuxMuxx, Mode2,
+            Mode2Dr, Mode2Gain,
+        },
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_due() {
+        let mut schedule = Recalibration::new(60_000, 1_000);
+        assert_eq!(schedule.due(0, 25_000), Some(RecalTrigger::First));
+        schedule.last = Some((u32::MAX - 10, 25_000));
+        assert_eq!(schedule.due(1_000, 25_900), None);
+        assert_eq!(schedule.due(1_000, 23_500), Some(RecalTrigger::Temperature));
+        assert_eq!(schedule.due(60_000, 25_000), Some(RecalTrigger::Interval));
+        schedule.reset();
+        assert_eq!(schedule.last_temperature_mc(), None);
+    }
+
+    #[test]
+    fn test_recalibrate_if_due() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        sim.set_offset(200);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.set_intref(true).unwrap();
+        let mode2 = Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain4, false).0;
+        let inpmux = InpMux::singleended(InpMuxMuxx::Ain0).0;
+        adc.write_reg(Mode2(mode2)).unwrap();
+        adc.write_reg(InpMux(inpmux)).unwrap();
+        adc.write_reg(Adc2Mux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.write_reg(Adc2Cfg::with(
+            Adc2CfgDr::Sps100,
+            Adc2CfgRef::InternalRef,
+            Adc2CfgGain::None,
+        ))
+        .unwrap();
+        adc.start2().unwrap();
+
+        let mut schedule = Recalibration::new(60_000, 2_000);
+        let event = adc
+            .recalibrate_if_due(&mut schedule, NoDelay, 10, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.trigger, RecalTrigger::First);
+        assert_eq!(event.ofcal1.0, 0);
+        assert_ne!(event.ofcal1.1, 0);
+        assert_ne!(event.ofcal2.unwrap().1, 0);
+        // Measured before the offset is removed, 200 uV is about 0.5 C
+        assert!((event.temperature_mc - 25_000).abs() < 1_000);
+        assert_eq!(adc.registers().get::<Mode2>().0, mode2);
+        assert_eq!(adc.registers().get::<InpMux>().0, inpmux);
+        assert_eq!(adc.registers().calibration(), sim.registers().calibration());
+        assert!(!sim.adc1_running() && sim.adc2_running());
+
+        adc.start1().unwrap();
+        let data = adc.read_adc1().unwrap();
+        assert!((data.to_microvolts() - 100_000).abs() <= 1);
+        assert!(adc
+            .recalibrate_if_due(&mut schedule, NoDelay, 10, 1_000)
+            .unwrap()
+            .is_none());
+        assert!(sim.adc1_running());
+
+        // Drift makes the offset change with temperature
+        sim.set_temperature(30_000);
+        sim.set_offset(260);
+        let event = adc
+            .recalibrate_if_due(&mut schedule, NoDelay, 10, 2_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.trigger, RecalTrigger::Temperature);
+        assert_ne!(event.ofcal1.0, event.ofcal1.1);
+        let data = adc.read_adc1().unwrap();
+        assert!((data.to_microvolts() - 100_000).abs() <= 1);
+
+        let event = adc
+            .recalibrate_if_due(&mut schedule, NoDelay, 10, 62_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.trigger, RecalTrigger::Interval);
+        assert_eq!(schedule.last_temperature_mc(), Some(event.temperature_mc));
+    }
+}
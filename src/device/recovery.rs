@@ -0,0 +1,171 @@
+use embedded_hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    commands::Command,
+    registers::{IdDev, Power, RegisterMap, ADC2CFG, GPIODAT, POWER, REGISTER_COUNT},
+};
+
+use super::{private, Ads126x, Ads126xError};
+
+/// [Power::reset] bit, ignored when comparing the POWER register
+const POWER_RESET: u8 = 1 << 4;
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Registers that hold configuration, ADC2 registers only exist on a ADS1263
+    fn config_end() -> usize {
+        if X::DEVICE == IdDev::Ads1263 {
+            REGISTER_COUNT
+        } else {
+            ADC2CFG as usize
+        }
+    }
+
+    /// Write the last written configuration and calibration back to the device
+    ///
+    /// Restarts ADCs that were started by command, conversions controlled by the START pin
+    /// resume on their own. Done automatically when a data read or [Self::check_registers] finds
+    /// the [Power::reset] flag set after it was cleared with [Self::clear_reset_flag]. Registers
+    /// changed by the calibration commands are only restored if read back with
+    /// [Self::read_calibration].
+    pub fn restore_configuration(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let mut regs = self.state.regs;
+        let mut power = regs.get::<Power>();
+        power.set_reset(false);
+        regs.set(power);
+        self.write_regs(POWER, &regs.0[POWER as usize..Self::config_end()])?;
+
+        let running = self.state.running;
+        if running[0] {
+            self.send_command(Command::Start1)?;
+        }
+        if running[1] {
+            self.send_command(Command::Start2)?;
+        }
+        self.state.resets = self.state.resets.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Verify that the device registers match the last written configuration
+    ///
+    /// Call periodically to detect a reset when the status byte is disabled or no data is read.
+    /// Fails with [Ads126xError::DeviceReset] after an unexpected reset, once the configuration
+    /// is restored, and with [Ads126xError::RegisterMismatch] if a register differs for another
+    /// reason. GPIODAT is not compared as it reads back the input pins.
+    pub fn check_registers(&mut self) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let end = Self::config_end();
+        let mut regs = RegisterMap::default();
+        self.read_regs(0, &mut regs.0[..end])?;
+
+        if regs.get::<Power>().reset() && !self.state.regs.get::<Power>().reset() {
+            warn!("unexpected device reset");
+            self.restore_configuration()?;
+            return Err(Ads126xError::DeviceReset);
+        }
+
+        for addr in POWER as usize..end {
+            let mask = match addr as u8 {
+                POWER => !POWER_RESET,
+                GPIODAT => 0,
+                _ => 0xFF,
+            };
+            let (expected, actual) = (self.state.regs.0[addr], regs.0[addr]);
+            if (expected ^ actual) & mask != 0 {
+                return Err(Ads126xError::RegisterMismatch {
+                    addr: addr as u8,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of unexpected resets the configuration was restored after
+    pub fn reset_count(&self) -> u32 {
+        self.state.resets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        device::Ads126xError,
+        registers::{
+            Calibration, InpMux, InpMuxMuxx, Interface, InterfaceCrc, Mode2, Mode2Gain, Power,
+        },
+        sim::SimAds126x,
+        Ads1263,
+    };
+
+    #[test]
+    fn test_reset_recovery() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.clear_reset_flag().unwrap();
+        let mut mode2 = adc.registers().get::<Mode2>();
+        mode2.set_gain(Mode2Gain::Gain8 as u8);
+        adc.write_reg(mode2).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        let cal = Calibration {
+            ofcal1: 0x10,
+            ..Default::default()
+        };
+        adc.write_calibration(&cal).unwrap();
+        adc.start1().unwrap();
+        adc.start2().unwrap();
+        let before = adc.read_adc1().unwrap();
+        assert_eq!(before.gain, 8);
+
+        sim.inject_reset();
+        assert!(matches!(adc.read_adc1(), Err(Ads126xError::DeviceReset)));
+        assert_eq!(sim.registers().0[1..], adc.registers().0[1..]);
+        assert!(!sim.registers().get::<Power>().reset());
+        assert!(sim.adc1_running() && sim.adc2_running());
+        assert_eq!(adc.read_adc1().unwrap().code, before.code);
+        assert_eq!(adc.reset_count(), 1);
+
+        // Periodic check without reading data
+        adc.check_registers().unwrap();
+        sim.inject_reset();
+        assert!(matches!(
+            adc.check_registers(),
+            Err(Ads126xError::DeviceReset)
+        ));
+        assert_eq!(sim.registers().0[1..], adc.registers().0[1..]);
+        adc.check_registers().unwrap();
+        assert_eq!(adc.reset_count(), 2);
+    }
+
+    #[test]
+    fn test_reset_recovery_crc() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.clear_reset_flag().unwrap();
+        let mut interface = Interface(0);
+        interface.set_status(true);
+        interface.set_crc(InterfaceCrc::Crc as u8);
+        adc.write_reg(interface).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.start1().unwrap();
+        let before = adc.read_adc1().unwrap();
+
+        // The device answers in the default checksum mode after the reset
+        sim.inject_reset();
+        assert!(matches!(adc.read_adc1(), Err(Ads126xError::DeviceReset)));
+        assert_eq!(sim.registers().0[1..], adc.registers().0[1..]);
+        assert_eq!(adc.read_adc1().unwrap().code, before.code);
+        assert_eq!(adc.reset_count(), 1);
+    }
+}
@@ -0,0 +1,225 @@
+use embedded_hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::OutputPin,
+};
+
+use crate::registers::{Adc2Cfg, Adc2CfgRef, Mode0, RefMux, RefMuxMuxN, RefMuxMuxP};
+
+use super::{ads1263::_Ads1263, private, Ads126x, Ads126xError, INTERNAL_REF_UV};
+
+/// ADC1 reference selection
+pub struct Reference {
+    /// Positive reference input
+    pub p: RefMuxMuxP,
+    /// Negative reference input
+    pub n: RefMuxMuxN,
+    /// Reference voltage (REFP - REFN) in uV
+    pub uv: u32,
+    /// Reverse reference polarity, see [Mode0::refrev]
+    pub reversed: bool,
+}
+
+impl Reference {
+    /// Internal 2.5 V reference
+    pub fn internal() -> Self {
+        Self {
+            p: RefMuxMuxP::InternalRefP,
+            n: RefMuxMuxN::InternalRefN,
+            uv: INTERNAL_REF_UV,
+            reversed: false,
+        }
+    }
+
+    /// External reference on AIN0/AIN1 with a voltage of `uv` uV
+    pub fn ain0_ain1(uv: u32) -> Self {
+        Self::external(RefMuxMuxP::Ain0, RefMuxMuxN::Ain1, uv)
+    }
+
+    /// External reference on AIN2/AIN3 with a voltage of `uv` uV
+    pub fn ain2_ain3(uv: u32) -> Self {
+        Self::external(RefMuxMuxP::Ain2, RefMuxMuxN::Ain3, uv)
+    }
+
+    /// External reference on AIN4/AIN5 with a voltage of `uv` uV
+    pub fn ain4_ain5(uv: u32) -> Self {
+        Self::external(RefMuxMuxP::Ain4, RefMuxMuxN::Ain5, uv)
+    }
+
+    /// Analog supply as reference with a supply voltage of `uv` uV
+    pub fn supply(uv: u32) -> Self {
+        Self::external(RefMuxMuxP::Avdd, RefMuxMuxN::Avss, uv)
+    }
+
+    /// Any combination of reference inputs with a voltage of `uv` uV
+    pub fn external(p: RefMuxMuxP, n: RefMuxMuxN, uv: u32) -> Self {
+        Self {
+            p,
+            n,
+            uv,
+            reversed: false,
+        }
+    }
+
+    /// Reverse reference polarity
+    pub fn reversed(self) -> Self {
+        Self {
+            reversed: true,
+            ..self
+        }
+    }
+
+    fn is_internal(&self) -> bool {
+        matches!(self.p, RefMuxMuxP::InternalRefP) || matches!(self.n, RefMuxMuxN::InternalRefN)
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Select ADC1 reference
+    ///
+    /// Writes [RefMux] and [Mode0::refrev] and enables the internal reference if used. Readings from ADC1
+    /// are converted using this reference from now on.
+    pub fn set_reference(
+        &mut self,
+        reference: Reference,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        if reference.is_internal() {
+            self.set_intref(true)?;
+        }
+
+        let mut mode0 = self.state.regs.get::<Mode0>();
+        mode0.set_refrev(reference.reversed);
+        self.write_reg(mode0)?;
+
+        self.write_reg(RefMux::with(reference.p, reference.n))?;
+        self.state.vref1_uv = reference.uv;
+        Ok(())
+    }
+
+    /// ADC1 reference voltage in uV
+    pub fn reference_uv(&self) -> u32 {
+        self.state.vref1_uv
+    }
+
+    /// Take the internal reference voltage for the ADCs [Self::registers] select it for
+    pub(super) fn sync_references(&mut self) {
+        let refmux = self.state.regs.get::<RefMux>();
+        if refmux.rmuxp() == RefMuxMuxP::InternalRefP as u8
+            && refmux.rmuxn() == RefMuxMuxN::InternalRefN as u8
+        {
+            self.state.vref1_uv = INTERNAL_REF_UV;
+        }
+        if self.state.regs.get::<Adc2Cfg>().ref2() == Adc2CfgRef::InternalRef as u8 {
+            self.state.vref2_uv = INTERNAL_REF_UV;
+        }
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr>
+    Ads126x<SPI, CS, DRDY, RST, _Ads1263, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+{
+    /// Select ADC2 reference, `uv` is the reference voltage in uV (ignored for [Adc2CfgRef::InternalRef])
+    ///
+    /// Writes [Adc2Cfg] and enables the internal reference if used. Readings from ADC2 are converted
+    /// using this reference from now on.
+    pub fn set_reference2(
+        &mut self,
+        reference: Adc2CfgRef,
+        uv: u32,
+    ) -> Result<(), Ads126xError<SpiErr, IoErr>> {
+        let uv = if let Adc2CfgRef::InternalRef = reference {
+            self.set_intref(true)?;
+            INTERNAL_REF_UV
+        } else {
+            uv
+        };
+
+        let mut cfg = self.state.regs.get::<Adc2Cfg>();
+        cfg.set_ref2(reference as u8);
+        self.write_reg(cfg)?;
+        self.state.vref2_uv = uv;
+        Ok(())
+    }
+
+    /// ADC2 reference voltage in uV
+    pub fn reference2_uv(&self) -> u32 {
+        self.state.vref2_uv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reference;
+    use crate::{
+        device::INTERNAL_REF_UV,
+        registers::{Adc2CfgRef, InpMux, InpMuxMuxx, Power},
+        sim::{NoDelay, SimAds126x},
+        Ads1263,
+    };
+
+    #[test]
+    fn test_reset_reference() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.set_reference(Reference::supply(5_000_000)).unwrap();
+        adc.set_reference2(Adc2CfgRef::AvddAvss, 5_000_000).unwrap();
+
+        // Back on the internal reference after a reset
+        adc.soft_reset(NoDelay).unwrap();
+        assert_eq!(adc.reference_uv(), INTERNAL_REF_UV);
+        assert_eq!(adc.reference2_uv(), INTERNAL_REF_UV);
+        adc.set_intref(true).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.start1().unwrap();
+        let data = adc.read_adc1().unwrap();
+        assert_eq!(data.vref_uv, INTERNAL_REF_UV);
+        assert_eq!(data.to_microvolts(), 100_000);
+    }
+
+    #[test]
+    fn test_sync_references() {
+        let sim = SimAds126x::ads1263();
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        let mut other = Ads1263::new(sim.spi(), sim.cs(), sim.drdy(), sim.rst());
+        adc.set_reference(Reference::supply(5_000_000)).unwrap();
+        adc.set_reference2(Adc2CfgRef::AvddAvss, 5_000_000).unwrap();
+
+        // External references are kept, the device doesn't know their voltage
+        adc.sync_registers().unwrap();
+        assert_eq!(adc.reference_uv(), 5_000_000);
+        assert_eq!(adc.reference2_uv(), 5_000_000);
+
+        other.set_reference(Reference::internal()).unwrap();
+        other.set_reference2(Adc2CfgRef::InternalRef, 0).unwrap();
+        adc.sync_registers().unwrap();
+        assert_eq!(adc.reference_uv(), INTERNAL_REF_UV);
+        assert_eq!(adc.reference2_uv(), INTERNAL_REF_UV);
+    }
+
+    #[test]
+    fn test_intref_from_device() {
+        let sim = SimAds126x::ads1263();
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        let mut other = Ads1263::new(sim.spi(), sim.cs(), sim.drdy(), sim.rst());
+        adc.set_intref(true).unwrap();
+
+        // Turned off behind the back of `adc`, its cache still has the reference on
+        other.set_intref(false).unwrap();
+        adc.set_reference(Reference::internal()).unwrap();
+        assert!(sim.registers().get::<Power>().intref());
+
+        other.set_intref(false).unwrap();
+        adc.set_reference2(Adc2CfgRef::InternalRef, 0).unwrap();
+        assert!(sim.registers().get::<Power>().intref());
+        assert_eq!(adc.reference2_uv(), 2_500_000);
+    }
+}
@@ -0,0 +1,394 @@
+use core::ops::RangeInclusive;
+
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    commands::Command,
+    registers::{
+        Adc2Cfg, Adc2CfgDr, Adc2CfgGain, Adc2CfgRef, Adc2Mux, Calibration, Id, IdDev, InpMux,
+        InpMuxMuxx, Interface, InterfaceCrc, Mode2, Mode2Dr, Mode2Gain, Power, RefMux, RefMuxMuxN,
+        RefMuxMuxP, ADC2CFG, OFCAL0, POWER, REGISTER_COUNT, SUPPORTED_REVISIONS, TDACP,
+    },
+};
+
+use super::{private, Ads126x, Ads126xError, Data, INTERNAL_REF_UV};
+
+/// TDACP magnitudes looped back against TDACN at mid supply, with their output as a fraction of
+/// AVDD − AVSS in units of 1e-7
+const TDAC_LEVELS: [(u8, i64); 4] = [
+    (0b01001, 9_000_000),
+    (0b01000, 7_000_000),
+    (0b11000, 3_000_000),
+    (0b11001, 1_000_000),
+];
+
+/// Patterns written to OFCAL0..OFCAL2 for the register readback check
+const PATTERNS: [[u8; 3]; 2] = [[0x55, 0xAA, 0x0F], [0xAA, 0x55, 0xF0]];
+
+/// Limits applied by [Ads126x::self_test]
+#[derive(Debug, Clone)]
+pub struct SelfTestLimits {
+    /// Analog supply in uV
+    pub avdd_uv: RangeInclusive<i64>,
+    /// Digital supply in uV
+    pub dvdd_uv: RangeInclusive<i64>,
+    /// Internal temperature in milli degrees Celsius
+    pub temperature_mc: RangeInclusive<i64>,
+    /// Largest deviation of a TDAC loopback from its expected voltage in uV
+    pub tdac_tolerance_uv: i64,
+}
+
+impl Default for SelfTestLimits {
+    /// 5 V ± 5 % analog and 2.7 V to 5.25 V digital supply, −40 to 125 °C, 1 % of AVDD for the TDAC
+    fn default() -> Self {
+        Self {
+            avdd_uv: 4_750_000..=5_250_000,
+            dvdd_uv: 2_700_000..=5_250_000,
+            temperature_mc: -40_000..=125_000,
+            tdac_tolerance_uv: 50_000,
+        }
+    }
+}
+
+/// Outcome of one check of [Ads126x::self_test] and the value it measured
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Check<T> {
+    pub pass: bool,
+    pub value: T,
+}
+
+/// TDAC output measured through the input multiplexer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Loopback {
+    /// Expected from the TDAC ratio and the measured AVDD
+    pub expected_uv: i64,
+    pub measured_uv: i64,
+}
+
+/// Results of [Ads126x::self_test]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestReport {
+    /// ID register, passes for the expected device and a supported revision
+    pub id: Check<u8>,
+    /// Number of registers not reading back the written test pattern
+    pub readback: Check<u8>,
+    /// Number of frames failing verification with checksum and with CRC framing
+    pub crc: Check<u8>,
+    pub avdd_uv: Check<i64>,
+    pub dvdd_uv: Check<i64>,
+    pub temperature_mc: Check<i64>,
+    /// Whether the reference alarm was raised during the measurements
+    pub ref_alarm: Check<bool>,
+    /// TDAC loopback on ADC1
+    pub tdac1: [Check<Loopback>; TDAC_LEVELS.len()],
+    /// TDAC loopback on ADC2, `None` on a ADS1262
+    pub tdac2: Option<[Check<Loopback>; TDAC_LEVELS.len()]>,
+}
+
+impl SelfTestReport {
+    /// All checks passed
+    pub fn passed(&self) -> bool {
+        self.id.pass
+            && self.readback.pass
+            && self.crc.pass
+            && self.avdd_uv.pass
+            && self.dvdd_uv.pass
+            && self.temperature_mc.pass
+            && self.ref_alarm.pass
+            && self.tdac1.iter().all(|c| c.pass)
+            && self.tdac2.is_none_or(|t| t.iter().all(|c| c.pass))
+    }
+}
+
+impl<SPI, CS, DRDY, RST, START, PWDN, SpiErr, IoErr, X> Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    X: private::Sealed,
+{
+    /// Check that the device works, polling DRDY every `us` microseconds
+    ///
+    /// Checks the ID, register write and readback, checksum and CRC framing, measures AVDD, DVDD
+    /// and the temperature with the internal reference while watching the reference alarm, and loops
+    /// the TDAC back through the input multiplexer of ADC1 and ADC2 (ADS1263 only). A failing check
+    /// is reported in the [SelfTestReport], communication errors other than checksum/CRC
+    /// mismatches are returned. All registers are restored afterwards and the ADCs are stopped.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn self_test<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+        limits: &SelfTestLimits,
+    ) -> Result<SelfTestReport, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let saved = self.read_registers()?;
+        let vrefs = (self.state.vref1_uv, self.state.vref2_uv);
+        let res = self.run_self_test(&mut delay, us, limits, saved.get::<Id>());
+
+        // ID is read-only and ADC2 only exists on a ADS1263
+        let (end, stop2) = if X::DEVICE == IdDev::Ads1263 {
+            (REGISTER_COUNT, self.send_command(Command::Stop2))
+        } else {
+            (ADC2CFG as usize, Ok(()))
+        };
+        let restore = stop2
+            .and_then(|_| self.stop1())
+            .and_then(|_| self.write_regs(POWER, &saved.0[POWER as usize..end]));
+        (self.state.vref1_uv, self.state.vref2_uv) = vrefs;
+        res.and_then(|r| restore.map(|_| r))
+    }
+
+    fn run_self_test<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+        limits: &SelfTestLimits,
+        id: Id,
+    ) -> Result<SelfTestReport, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let id = Check {
+            pass: id.device() == Some(X::DEVICE) && SUPPORTED_REVISIONS.contains(&id.rev_id()),
+            value: id.0,
+        };
+
+        let mut mismatches = 0;
+        for pattern in PATTERNS {
+            let mut read = [0; 3];
+            self.write_regs(OFCAL0, &pattern)?;
+            self.read_regs(OFCAL0, &mut read)?;
+            mismatches += read.iter().zip(pattern).filter(|(r, p)| **r != *p).count() as u8;
+        }
+        let readback = Check {
+            pass: mismatches == 0,
+            value: mismatches,
+        };
+
+        // Internal reference, gain 1 and no calibration
+        let mut power = self.state.regs.get::<Power>();
+        power.set_intref(true);
+        self.write_reg(power)?;
+        self.write_reg(RefMux::with(
+            RefMuxMuxP::InternalRefP,
+            RefMuxMuxN::InternalRefN,
+        ))?;
+        self.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::None, false))?;
+        self.write_regs(OFCAL0, &Calibration::default().to_bytes().0)?;
+        self.write_regs(TDACP, &[0, 0])?;
+        self.state.vref1_uv = INTERNAL_REF_UV;
+        self.start1()?;
+
+        let mut bad_frames = 0;
+        for crc in [InterfaceCrc::Checksum, InterfaceCrc::Crc] {
+            let mut interface = Interface(0);
+            interface.set_status(true);
+            interface.set_crc(crc as u8);
+            self.write_reg(interface)?;
+            match self.convert1(delay, us, InpMux::analog_power()) {
+                Ok(_) => {}
+                Err(Ads126xError::Crc(_)) => bad_frames += 1,
+                Err(e) => return Err(e),
+            }
+        }
+        let crc = Check {
+            pass: bad_frames == 0,
+            value: bad_frames,
+        };
+
+        let mut alarm = false;
+        let mut measure = |this: &mut Self, mux| {
+            let data = this.convert1(delay, us, mux)?;
+            alarm |= data.status.ref_alm();
+            Ok(data)
+        };
+        let avdd = measure(self, InpMux::analog_power())?.to_microvolts() * 4;
+        let dvdd = measure(self, InpMux::digital_power())?.to_microvolts() * 4;
+        let temperature = measure(self, InpMux::temperature())?
+            .to_millicelsius()
+            .into();
+
+        let mut tdac1 = [Check {
+            pass: false,
+            value: Loopback {
+                expected_uv: 0,
+                measured_uv: 0,
+            },
+        }; TDAC_LEVELS.len()];
+        let mut tdac2 = tdac1;
+        for (check, (mag, ratio)) in tdac1.iter_mut().zip(TDAC_LEVELS) {
+            self.write_regs(TDACP, &[mag])?;
+            let data = measure(self, InpMux::tdac())?;
+            *check = loopback(avdd, ratio, data.to_microvolts(), limits);
+        }
+
+        let tdac2 = if X::DEVICE == IdDev::Ads1263 {
+            self.write_reg(Adc2Cfg::with(
+                Adc2CfgDr::Sps100,
+                Adc2CfgRef::InternalRef,
+                Adc2CfgGain::None,
+            ))?;
+            self.write_reg(Adc2Mux::differential(InpMuxMuxx::Tdac, InpMuxMuxx::Tdac))?;
+            self.state.vref2_uv = INTERNAL_REF_UV;
+            self.send_command(Command::Start2)?;
+            for (check, (mag, ratio)) in tdac2.iter_mut().zip(TDAC_LEVELS) {
+                self.write_regs(TDACP, &[mag])?;
+                let data = self.convert2(delay, us)?;
+                *check = loopback(avdd, ratio, data.to_microvolts(), limits);
+            }
+            Some(tdac2)
+        } else {
+            None
+        };
+
+        Ok(SelfTestReport {
+            id,
+            readback,
+            crc,
+            avdd_uv: Check {
+                pass: limits.avdd_uv.contains(&avdd),
+                value: avdd,
+            },
+            dvdd_uv: Check {
+                pass: limits.dvdd_uv.contains(&dvdd),
+                value: dvdd,
+            },
+            temperature_mc: Check {
+                pass: limits.temperature_mc.contains(&temperature),
+                value: temperature,
+            },
+            ref_alarm: Check {
+                pass: !alarm,
+                value: alarm,
+            },
+            tdac1,
+            tdac2,
+        })
+    }
+
+    /// Select `mux` on ADC1 and read the second conversion
+    pub(super) fn convert1<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+        mux: InpMux,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        self.write_reg(mux)?;
+        let mut data = Data::default();
+        for _ in 0..2 {
+            while !self.drdy()? {
+                delay.delay_us(us);
+            }
+            data = self.read_adc1()?;
+        }
+        Ok(data)
+    }
+
+    /// Read the second new ADC2 conversion
+    fn convert2<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        us: u32,
+    ) -> Result<Data, Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        let mut fresh = 0;
+        loop {
+            let data = self.read_data(Some(Command::RData2))?;
+            if data.status.adc2() {
+                fresh += 1;
+                if fresh == 2 {
+                    return Ok(data);
+                }
+            } else {
+                delay.delay_us(us);
+            }
+        }
+    }
+}
+
+/// Compare a TDAC loopback with the output expected at `ratio` of `avdd_uv`
+fn loopback(
+    avdd_uv: i64,
+    ratio: i64,
+    measured_uv: i64,
+    limits: &SelfTestLimits,
+) -> Check<Loopback> {
+    // TDACN is at mid supply
+    let expected_uv = avdd_uv * (ratio - 5_000_000) / 10_000_000;
+    Check {
+        pass: (measured_uv - expected_uv).abs() <= limits.tdac_tolerance_uv,
+        value: Loopback {
+            expected_uv,
+            measured_uv,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SelfTestLimits;
+    use crate::{
+        registers::{Mode2, RegisterMap},
+        sim::{Faults, NoDelay, SimAds126x},
+        Ads1262, Ads1263,
+    };
+
+    #[test]
+    fn test_self_test() {
+        let sim = SimAds126x::ads1263();
+        sim.set_supplies(5_000_000, 3_300_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.write_reg(Mode2(0x45)).unwrap();
+        let before = adc.read_registers().unwrap();
+
+        let report = adc
+            .self_test(NoDelay, 10, &SelfTestLimits::default())
+            .unwrap();
+        assert!(report.passed(), "{report:?}");
+        assert_eq!(report.avdd_uv.value, 5_000_000);
+        assert_eq!(report.tdac1[0].value.expected_uv, 2_000_000);
+        assert!(report.tdac2.is_some());
+        assert_eq!(adc.read_registers().unwrap().0[1..], before.0[1..]);
+        assert!(!sim.adc1_running() && !sim.adc2_running());
+
+        sim.set_supplies(4_000_000, 3_300_000);
+        sim.set_faults(Faults {
+            status: 1 << 4,
+            ..Faults::default()
+        });
+        let report = adc
+            .self_test(NoDelay, 10, &SelfTestLimits::default())
+            .unwrap();
+        assert!(!report.avdd_uv.pass && !report.ref_alarm.pass && !report.passed());
+        assert!(report.tdac1.iter().all(|c| c.pass));
+
+        let sim = SimAds126x::ads1262();
+        let mut adc = Ads1262::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        let report = adc
+            .self_test(NoDelay, 10, &SelfTestLimits::default())
+            .unwrap();
+        assert!(report.passed() && report.tdac2.is_none());
+        assert_eq!(adc.registers().0[..0x15], RegisterMap::default().0[..0x15]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Frequency response model of the ADC1 digital filter
+//!
+//! The modulator runs at 921.6 kHz (internal 7.3728 MHz clock). At 38400, 19200 and 14400 SPS the
+//! filter is a single sinc5 stage decimating to the data rate, the [Mode1Filter] setting has no
+//! effect. At lower data rates a sinc5 stage decimates to 28.8 kHz, followed by the selected sinc1
+//! to sinc4 stage. The FIR filter (2.5 to 20 SPS only) is approximated by cascaded averages with
+//! notches at 50 Hz, 60 Hz and the data rate.
+//!
+//! Input chopping averages pairs of conversions with swapped inputs, which halves the output data
+//! rate, adds notches at odd multiples of half the data rate and needs one more conversion to settle.
+//!
+//! ```
+//! use ads126x::{filter::{FilterConfig, Mains, Requirements}, registers::{Mode1Filter, Mode2Dr}};
+//!
+//! let config = FilterConfig::new(Mode1Filter::Sinc1, Mode2Dr::Sps10, false);
+//! assert!(config.line_rejection_db(50.0, 0.0) > 100.0);
+//!
+//! let req = Requirements {
+//!     bandwidth_hz: 5.0,
+//!     mains: Mains::Both,
+//!     rejection_db: 60.0,
+//!     tolerance_hz: 0.5,
+//! };
+//! let best = ads126x::filter::recommend(&req).next().unwrap();
+//! assert!(best.bandwidth_hz() >= 5.0);
+//! ```
+
+use core::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use libm::{ceil, cos, log10, sin};
+
+use crate::registers::{Mode0, Mode1, Mode1Filter, Mode2, Mode2Dr, RegisterMap};
+
+/// Modulator frequency in Hz
+pub const MODULATOR_HZ: f64 = 921_600.0;
+/// Output rate of the sinc5 stage at data rates up to 7200 SPS
+const SINC5_HZ: f64 = 28_800.0;
+
+/// Data rates in order of [Mode2::dr]
+const RATES: [Mode2Dr; 16] = [
+    Mode2Dr::Sps2_5,
+    Mode2Dr::Sps5,
+    Mode2Dr::Sps10,
+    Mode2Dr::Sps16_6,
+    Mode2Dr::Sps20,
+    Mode2Dr::Sps50,
+    Mode2Dr::Sps60,
+    Mode2Dr::Sps100,
+    Mode2Dr::Sps400,
+    Mode2Dr::Sps1200,
+    Mode2Dr::Sps2400,
+    Mode2Dr::Sps4800,
+    Mode2Dr::Sps7200,
+    Mode2Dr::Sps14400,
+    Mode2Dr::Sps19200,
+    Mode2Dr::Sps38400,
+];
+/// Filters in order of [Mode1::filter]
+const FILTERS: [Mode1Filter; 5] = [
+    Mode1Filter::Sinc1,
+    Mode1Filter::Sinc2,
+    Mode1Filter::Sinc3,
+    Mode1Filter::Sinc4,
+    Mode1Filter::Fir,
+];
+
+/// Data rate in Hz
+pub fn rate_hz(dr: Mode2Dr) -> f64 {
+    match dr {
+        Mode2Dr::Sps2_5 => 2.5,
+        Mode2Dr::Sps5 => 5.0,
+        Mode2Dr::Sps10 => 10.0,
+        Mode2Dr::Sps16_6 => 50.0 / 3.0,
+        Mode2Dr::Sps20 => 20.0,
+        Mode2Dr::Sps50 => 50.0,
+        Mode2Dr::Sps60 => 60.0,
+        Mode2Dr::Sps100 => 100.0,
+        Mode2Dr::Sps400 => 400.0,
+        Mode2Dr::Sps1200 => 1_200.0,
+        Mode2Dr::Sps2400 => 2_400.0,
+        Mode2Dr::Sps4800 => 4_800.0,
+        Mode2Dr::Sps7200 => 7_200.0,
+        Mode2Dr::Sps14400 => 14_400.0,
+        Mode2Dr::Sps19200 => 19_200.0,
+        Mode2Dr::Sps38400 => 38_400.0,
+    }
+}
+
+/// Magnitude of an averaging filter of `decimation` samples at `f_in`, raised to `order`
+fn sinc(f: f64, decimation: f64, f_in: f64, order: i32) -> f64 {
+    let x = PI * f / f_in;
+    let den = decimation * sin(x);
+    let h = if den.abs() < 1e-12 {
+        1.0
+    } else {
+        (sin(decimation * x) / den).abs()
+    };
+    (0..order).fold(1.0, |acc, _| acc * h)
+}
+
+/// Filter, data rate and chop setting of ADC1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FilterConfig {
+    pub filter: Mode1Filter,
+    pub rate: Mode2Dr,
+    /// Input chopping ([Mode0::chop] 1 or 3)
+    pub chop: bool,
+}
+
+impl FilterConfig {
+    pub fn new(filter: Mode1Filter, rate: Mode2Dr, chop: bool) -> Self {
+        Self { filter, rate, chop }
+    }
+
+    /// Configuration in MODE0, MODE1 and MODE2, `None` if MODE1 holds a reserved filter
+    pub fn from_registers(regs: &RegisterMap) -> Option<Self> {
+        let filter = *FILTERS.get(usize::from(regs.get::<Mode1>().filter()))?;
+        let rate = RATES[usize::from(regs.get::<Mode2>().dr())];
+        let chop = regs.get::<Mode0>().chop() & 1 != 0;
+        Some(Self { filter, rate, chop })
+    }
+
+    /// Write the configuration into MODE0, MODE1 and MODE2, keeping the other fields
+    pub fn apply(&self, regs: &mut RegisterMap) {
+        let mut mode0 = regs.get::<Mode0>();
+        mode0.set_chop((mode0.chop() & 2) | u8::from(self.chop));
+        regs.set(mode0);
+        let mut mode1 = regs.get::<Mode1>();
+        mode1.set_filter(self.filter as u8);
+        regs.set(mode1);
+        let mut mode2 = regs.get::<Mode2>();
+        mode2.set_dr(self.rate as u8);
+        regs.set(mode2);
+    }
+
+    /// The FIR filter is only available at data rates up to 20 SPS
+    pub fn is_supported(&self) -> bool {
+        self.filter != Mode1Filter::Fir || rate_hz(self.rate) <= 20.0
+    }
+
+    /// Output data rate in Hz, half the configured data rate with chopping
+    pub fn output_rate_hz(&self) -> f64 {
+        if self.chop {
+            rate_hz(self.rate) / 2.0
+        } else {
+            rate_hz(self.rate)
+        }
+    }
+
+    /// Magnitude response at `f_hz` relative to DC
+    pub fn magnitude(&self, f_hz: f64) -> f64 {
+        let fd = rate_hz(self.rate);
+        let mut h = if fd >= 14_400.0 {
+            sinc(f_hz, MODULATOR_HZ / fd, MODULATOR_HZ, 5)
+        } else {
+            let stage2 = match self.filter {
+                Mode1Filter::Sinc1 => sinc(f_hz, SINC5_HZ / fd, SINC5_HZ, 1),
+                Mode1Filter::Sinc2 => sinc(f_hz, SINC5_HZ / fd, SINC5_HZ, 2),
+                Mode1Filter::Sinc3 => sinc(f_hz, SINC5_HZ / fd, SINC5_HZ, 3),
+                Mode1Filter::Sinc4 => sinc(f_hz, SINC5_HZ / fd, SINC5_HZ, 4),
+                Mode1Filter::Fir => {
+                    sinc(f_hz, SINC5_HZ / 50.0, SINC5_HZ, 1)
+                        * sinc(f_hz, SINC5_HZ / 60.0, SINC5_HZ, 1)
+                        * sinc(f_hz, SINC5_HZ / fd, SINC5_HZ, 1)
+                }
+            };
+            sinc(f_hz, MODULATOR_HZ / SINC5_HZ, MODULATOR_HZ, 5) * stage2
+        };
+        if self.chop {
+            h *= cos(PI * f_hz / fd).abs();
+        }
+        h
+    }
+
+    /// Magnitude response at `f_hz` in dB relative to DC
+    pub fn magnitude_db(&self, f_hz: f64) -> f64 {
+        20.0 * log10(self.magnitude(f_hz))
+    }
+
+    /// Frequencies where the response is zero, in ascending order up to `max_hz`
+    pub fn notches(&self, max_hz: f64) -> Notches {
+        let fd = rate_hz(self.rate);
+        let step = if self.chop { fd / 2.0 } else { fd };
+        let mut bases = [step, f64::INFINITY, f64::INFINITY];
+        if self.filter == Mode1Filter::Fir && fd < 14_400.0 {
+            bases[1] = 50.0;
+            bases[2] = 60.0;
+        }
+        Notches {
+            bases,
+            multiples: [1; 3],
+            max_hz,
+        }
+    }
+
+    /// -3 dB bandwidth in Hz
+    pub fn bandwidth_hz(&self) -> f64 {
+        let (mut lo, mut hi) = (0.0, self.notches(f64::MAX).next().unwrap_or(MODULATOR_HZ));
+        for _ in 0..60 {
+            let mid = (lo + hi) / 2.0;
+            if self.magnitude(mid) > FRAC_1_SQRT_2 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Worst-case rejection in dB of a line frequency `line_hz` that may deviate by `tolerance_hz`
+    pub fn line_rejection_db(&self, line_hz: f64, tolerance_hz: f64) -> f64 {
+        const STEPS: i32 = 100;
+        let worst = (0..=STEPS)
+            .map(|i| line_hz - tolerance_hz + 2.0 * tolerance_hz * f64::from(i) / f64::from(STEPS))
+            .map(|f| self.magnitude(f))
+            .fold(0.0, f64::max);
+        -20.0 * log10(worst)
+    }
+
+    /// Conversions (at the configured data rate) until the output settles after an input step
+    pub fn settling_conversions(&self) -> u32 {
+        let fd = rate_hz(self.rate);
+        let conversions = if fd >= 14_400.0 {
+            1
+        } else {
+            match self.filter {
+                Mode1Filter::Sinc1 => 1,
+                Mode1Filter::Sinc2 => 2,
+                Mode1Filter::Sinc3 => 3,
+                Mode1Filter::Sinc4 => 4,
+                Mode1Filter::Fir => ceil((1.0 / 50.0 + 1.0 / 60.0 + 1.0 / fd) * fd) as u32,
+            }
+        };
+        conversions + u32::from(self.chop)
+    }
+
+    /// Settling time after an input step in us
+    pub fn settling_time_us(&self) -> u32 {
+        self.settling_conversions() * self.rate.period_us()
+    }
+}
+
+/// Notch frequencies of a [FilterConfig]
+pub struct Notches {
+    bases: [f64; 3],
+    multiples: [u32; 3],
+    max_hz: f64,
+}
+
+impl Iterator for Notches {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let f = (0..3)
+            .map(|i| self.bases[i] * f64::from(self.multiples[i]))
+            .fold(f64::INFINITY, f64::min);
+        if f > self.max_hz {
+            return None;
+        }
+        // Advance every series at this frequency so that shared notches are reported once
+        for i in 0..3 {
+            if (self.bases[i] * f64::from(self.multiples[i]) - f).abs() <= f * 1e-9 {
+                self.multiples[i] += 1;
+            }
+        }
+        Some(f)
+    }
+}
+
+/// Line frequencies to reject
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mains {
+    Hz50,
+    Hz60,
+    Both,
+}
+
+impl Mains {
+    pub fn frequencies(&self) -> &'static [f64] {
+        match self {
+            Mains::Hz50 => &[50.0],
+            Mains::Hz60 => &[60.0],
+            Mains::Both => &[50.0, 60.0],
+        }
+    }
+}
+
+/// Requirements for [recommend]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Requirements {
+    /// Minimum -3 dB bandwidth in Hz
+    pub bandwidth_hz: f64,
+    pub mains: Mains,
+    /// Minimum line rejection in dB, 0 if not needed
+    pub rejection_db: f64,
+    /// Deviation of the line frequency in Hz
+    pub tolerance_hz: f64,
+}
+
+impl Requirements {
+    pub fn is_met(&self, config: &FilterConfig) -> bool {
+        config.is_supported()
+            && config.bandwidth_hz() >= self.bandwidth_hz
+            && self
+                .mains
+                .frequencies()
+                .iter()
+                .all(|f| config.line_rejection_db(*f, self.tolerance_hz) >= self.rejection_db)
+    }
+}
+
+const CONFIG_COUNT: usize = FILTERS.len() * RATES.len() * 2;
+
+/// Configurations meeting the [Requirements], fastest settling first
+pub fn recommend(req: &Requirements) -> Recommendations {
+    let mut configs = [FilterConfig::new(Mode1Filter::Sinc1, Mode2Dr::Sps2_5, false); CONFIG_COUNT];
+    let mut len = 0;
+    for filter in FILTERS {
+        for rate in RATES {
+            for chop in [false, true] {
+                let config = FilterConfig::new(filter, rate, chop);
+                if req.is_met(&config) {
+                    configs[len] = config;
+                    len += 1;
+                }
+            }
+        }
+    }
+    configs[..len].sort_unstable_by(|a, b| {
+        a.settling_time_us()
+            .cmp(&b.settling_time_us())
+            .then(b.bandwidth_hz().total_cmp(&a.bandwidth_hz()))
+    });
+    Recommendations {
+        configs,
+        len,
+        pos: 0,
+    }
+}
+
+/// Result of [recommend]
+pub struct Recommendations {
+    configs: [FilterConfig; CONFIG_COUNT],
+    len: usize,
+    pos: usize,
+}
+
+impl Iterator for Recommendations {
+    type Item = FilterConfig;
+
+    fn next(&mut self) -> Option<FilterConfig> {
+        let config = self.configs[..self.len].get(self.pos).copied()?;
+        self.pos += 1;
+        Some(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn test_period() {
+        for dr in RATES {
+            let us = 1e6 / rate_hz(dr);
+            assert!((f64::from(dr.period_us()) - us).abs() <= 0.5, "{dr:?}");
+        }
+    }
+
+    #[test]
+    fn test_response() {
+        let sinc1 = FilterConfig::new(Mode1Filter::Sinc1, Mode2Dr::Sps10, false);
+        assert!((sinc1.bandwidth_hz() - 4.43).abs() < 0.01);
+        assert!(sinc1.magnitude(0.0) > 0.999);
+        assert!(sinc1.line_rejection_db(60.0, 0.0) > 100.0);
+        let mut notches = sinc1.notches(35.0);
+        assert_eq!(notches.next(), Some(10.0));
+        assert_eq!(notches.last(), Some(30.0));
+
+        let sinc4 = FilterConfig::new(Mode1Filter::Sinc4, Mode2Dr::Sps10, false);
+        assert!(sinc4.bandwidth_hz() < sinc1.bandwidth_hz());
+        assert!(sinc4.line_rejection_db(50.0, 1.0) > sinc1.line_rejection_db(50.0, 1.0));
+        assert_eq!(sinc4.settling_conversions(), 4);
+        assert_eq!(sinc4.settling_time_us(), 400_000);
+
+        let chop = FilterConfig::new(Mode1Filter::Sinc1, Mode2Dr::Sps100, true);
+        assert_eq!(
+            chop.notches(150.0).collect::<Vec<_>>(),
+            [50.0, 100.0, 150.0]
+        );
+        assert_eq!(chop.output_rate_hz(), 50.0);
+
+        let fir = FilterConfig::new(Mode1Filter::Fir, Mode2Dr::Sps20, false);
+        assert!(
+            fir.line_rejection_db(50.0, 0.0) > 100.0 && fir.line_rejection_db(60.0, 0.0) > 100.0
+        );
+        assert!(!FilterConfig::new(Mode1Filter::Fir, Mode2Dr::Sps400, false).is_supported());
+
+        let mut regs = RegisterMap::default();
+        chop.apply(&mut regs);
+        assert_eq!(FilterConfig::from_registers(&regs), Some(chop));
+        assert_eq!(
+            FilterConfig::from_registers(&RegisterMap::default()),
+            Some(FilterConfig::new(Mode1Filter::Fir, Mode2Dr::Sps20, false))
+        );
+    }
+
+    #[test]
+    fn test_recommend() {
+        let req = Requirements {
+            bandwidth_hz: 3.0,
+            mains: Mains::Both,
+            rejection_db: 80.0,
+            tolerance_hz: 0.0,
+        };
+        let mut configs = recommend(&req);
+        let best = configs.next().unwrap();
+        assert!(req.is_met(&best));
+        assert!(configs.all(|c| c.settling_time_us() >= best.settling_time_us()));
+    }
+}
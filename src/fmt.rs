@@ -0,0 +1,230 @@
+//! Logging of SPI traffic using [defmt] and/or [log]
+//!
+//! Commands and register writes are logged at `debug` level, register reads and data frames at
+//! `trace` level and checksum/CRC errors at `warn` level. The level of each kind of traffic can be
+//! changed at runtime with [LogCategory::set_level], the levels are then filtered the usual way,
+//! e.g. with `DEFMT_LOG` or [log::set_max_level]. Without the `defmt` and `log` features the macros
+//! expand to nothing.
+
+#![allow(unused_macros)]
+
+use core::fmt;
+#[cfg(any(feature = "defmt", feature = "log"))]
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "defmt")]
+use crate::device::Status;
+use crate::metadata::register_at;
+use crate::registers::*;
+
+macro_rules! log_at {
+    ($level:ident, $s:literal $(, $x:expr)* $(,)?) => {{
+        #[cfg(feature = "log")]
+        ::log::$level!(target: "ads126x", $s $(, $x)*);
+        #[cfg(feature = "defmt")]
+        ::defmt::$level!($s $(, $x)*);
+        #[cfg(not(any(feature = "log", feature = "defmt")))]
+        let _ = ($(&$x),*);
+    }};
+}
+
+/// Log SPI traffic of a [LogCategory] at the level set for it
+macro_rules! log_in {
+    ($category:ident, $s:literal $(, $x:expr)* $(,)?) => {{
+        #[cfg(any(feature = "log", feature = "defmt"))]
+        match $crate::fmt::LogCategory::$category.level() {
+            $crate::fmt::LogLevel::Off => {}
+            $crate::fmt::LogLevel::Warn => log_at!(warn, $s $(, $x)*),
+            $crate::fmt::LogLevel::Info => log_at!(info, $s $(, $x)*),
+            $crate::fmt::LogLevel::Debug => log_at!(debug, $s $(, $x)*),
+            $crate::fmt::LogLevel::Trace => log_at!(trace, $s $(, $x)*),
+        }
+        #[cfg(not(any(feature = "log", feature = "defmt")))]
+        let _ = ($(&$x),*);
+    }};
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { log_at!(info, $($arg)*) };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => { log_at!(warn, $($arg)*) };
+}
+
+/// Kind of SPI traffic with its own log level
+#[cfg(any(feature = "defmt", feature = "log"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogCategory {
+    /// Commands, `debug` by default
+    Command,
+    /// Register writes, `debug` by default
+    RegisterWrite,
+    /// Register reads, `trace` by default
+    RegisterRead,
+    /// Data frames read with or without the RDATA commands, `trace` by default
+    DataFrame,
+}
+
+/// Level a [LogCategory] is logged at, traffic is never an error
+#[cfg(any(feature = "defmt", feature = "log"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogLevel {
+    /// Not logged
+    Off,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[cfg(any(feature = "defmt", feature = "log"))]
+const LEVELS: [LogLevel; 5] = [
+    LogLevel::Off,
+    LogLevel::Warn,
+    LogLevel::Info,
+    LogLevel::Debug,
+    LogLevel::Trace,
+];
+
+/// Levels by [LogCategory]
+#[cfg(any(feature = "defmt", feature = "log"))]
+static CATEGORY_LEVELS: [AtomicU8; 4] = [
+    AtomicU8::new(LogLevel::Debug as u8),
+    AtomicU8::new(LogLevel::Debug as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+    AtomicU8::new(LogLevel::Trace as u8),
+];
+
+#[cfg(any(feature = "defmt", feature = "log"))]
+impl LogCategory {
+    /// Log this category at `level`, applies to all devices
+    pub fn set_level(self, level: LogLevel) {
+        CATEGORY_LEVELS[self as usize].store(level as u8, Ordering::Relaxed);
+    }
+
+    /// Level this category is logged at
+    pub fn level(self) -> LogLevel {
+        LEVELS[usize::from(CATEGORY_LEVELS[self as usize].load(Ordering::Relaxed))]
+    }
+}
+
+/// Register content at an address, formatted with its decoded fields
+#[derive(Copy, Clone)]
+pub(crate) struct RegisterValue(pub u8, pub u8);
+
+impl fmt::Debug for RegisterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let RegisterValue(addr, b) = *self;
+        match addr {
+            ID => Id(b).fmt(f),
+            POWER => Power(b).fmt(f),
+            INTERFACE => Interface(b).fmt(f),
+            MODE0 => Mode0(b).fmt(f),
+            MODE1 => Mode1(b).fmt(f),
+            MODE2 => Mode2(b).fmt(f),
+            INPMUX => InpMux(b).fmt(f),
+            IDACMUX => IdacMux(b).fmt(f),
+            IDACMAG => IdacMag(b).fmt(f),
+            REFMUX => RefMux(b).fmt(f),
+            TDACP => TdacP(b).fmt(f),
+            TDACN => TdacN(b).fmt(f),
+            GPIOCON => GpioCon(b).fmt(f),
+            GPIODIR => GpioDir(b).fmt(f),
+            GPIODAT => GpioDat(b).fmt(f),
+            ADC2CFG => Adc2Cfg(b).fmt(f),
+            ADC2MUX => Adc2Mux(b).fmt(f),
+            _ => match register_at(addr) {
+                Some(info) => write!(f, "{}({b:#04x})", info.name),
+                None => write!(f, "{addr:#04x}({b:#04x})"),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for RegisterValue {
+    fn format(&self, f: defmt::Formatter) {
+        let RegisterValue(addr, b) = *self;
+        match addr {
+            ID => Id(b).format(f),
+            POWER => Power(b).format(f),
+            INTERFACE => Interface(b).format(f),
+            MODE0 => Mode0(b).format(f),
+            MODE1 => Mode1(b).format(f),
+            MODE2 => Mode2(b).format(f),
+            INPMUX => InpMux(b).format(f),
+            IDACMUX => IdacMux(b).format(f),
+            IDACMAG => IdacMag(b).format(f),
+            REFMUX => RefMux(b).format(f),
+            TDACP => TdacP(b).format(f),
+            TDACN => TdacN(b).format(f),
+            GPIOCON => GpioCon(b).format(f),
+            GPIODIR => GpioDir(b).format(f),
+            GPIODAT => GpioDat(b).format(f),
+            ADC2CFG => Adc2Cfg(b).format(f),
+            ADC2MUX => Adc2Mux(b).format(f),
+            _ => match register_at(addr) {
+                Some(info) => defmt::write!(f, "{=str}({=u8:#04x})", info.name, b),
+                None => defmt::write!(f, "{=u8:#04x}({=u8:#04x})", addr, b),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! impl_format {
+    ($typ:ident { $($field:ident: $fty:ty),* $(,)? }) => {
+        impl defmt::Format for $typ {
+            fn format(&self, f: defmt::Formatter) {
+                defmt::write!(f, "{=str} {{ raw: {=u8:#04x}", stringify!($typ), self.0);
+                $(defmt::write!(f, ", {=str}: {}", stringify!($field), self.$field());)*
+                defmt::write!(f, " }}");
+            }
+        }
+    };
+}
+
+#[cfg(feature = "defmt")]
+for_each_register!(impl_format);
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for RegisterMap {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "RegisterMap {{");
+        for (addr, b) in self.0.iter().enumerate() {
+            defmt::write!(f, " {}", RegisterValue(addr as u8, *b));
+        }
+        defmt::write!(f, " }}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::format;
+
+    use super::RegisterValue;
+
+    #[test]
+    fn test_register_value() {
+        let s = format!("{:?}", RegisterValue(0x05, 0x38));
+        assert!(
+            s.starts_with("Mode2") && s.contains("dr: 8") && s.contains("gain: 3"),
+            "{s}"
+        );
+        assert_eq!(format!("{:?}", RegisterValue(0x08, 0x12)), "OFCAL1(0x12)");
+    }
+    #[test]
+    #[cfg(any(feature = "defmt", feature = "log"))]
+    fn test_log_level() {
+        use super::{LogCategory, LogLevel};
+
+        assert_eq!(LogCategory::Command.level(), LogLevel::Debug);
+        assert_eq!(LogCategory::RegisterRead.level(), LogLevel::Trace);
+        LogCategory::RegisterWrite.set_level(LogLevel::Off);
+        assert_eq!(LogCategory::RegisterWrite.level(), LogLevel::Off);
+        LogCategory::RegisterWrite.set_level(LogLevel::Debug);
+    }
+}
@@ -1,19 +1,55 @@
 #![no_std]
 use device::Ads126x;
 
+#[macro_use]
+mod fmt;
+
+#[cfg(any(feature = "defmt", feature = "log"))]
+pub use fmt::{LogCategory, LogLevel};
+
+pub mod capture;
 /// Commands to send
 mod commands;
 pub mod crc8;
 /// Generic ADS126x device drivers
 pub mod device;
+#[cfg(feature = "float")]
+pub mod filter;
+pub mod metadata;
+pub mod processing;
 pub mod registers;
+pub mod replay;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+pub mod sync;
+#[cfg(feature = "uom")]
+pub mod units;
 
 /// A ADS1262 device
-pub type Ads1262<SPI, CS, DRDY, RST> = Ads126x<SPI, CS, DRDY, RST, device::ads1262::_Ads1262>;
+pub type Ads1262<SPI, CS, DRDY, RST, START = StartNoConnection, PWDN = PwdnNoConnection> =
+    Ads126x<SPI, CS, DRDY, RST, device::ads1262::_Ads1262, START, PWDN>;
 
 /// A ADS1263 device
-pub type Ads1263<SPI, CS, DRDY, RST> = Ads126x<SPI, CS, DRDY, RST, device::ads1263::_Ads1263>;
+pub type Ads1263<SPI, CS, DRDY, RST, START = StartNoConnection, PWDN = PwdnNoConnection> =
+    Ads126x<SPI, CS, DRDY, RST, device::ads1263::_Ads1263, START, PWDN>;
 
 /// Dummy pin used for [Ads126x] DRDY pin when DRDY is not connected.
 ///
 pub struct DrdyNoConnection;
+
+/// Dummy pin used for [Ads126x] RST pin when RST is not connected (tied high).
+///
+/// Use [Ads126x::soft_reset] to reset the device instead.
+pub struct RstNoConnection;
+
+/// Dummy pin used for [Ads126x] START pin when START is not connected (tied low).
+///
+/// Conversions are started using the START1/START2 commands instead.
+pub struct StartNoConnection;
+
+/// Dummy pin used for [Ads126x] PWDN pin when PWDN is not connected (tied high).
+///
+/// [Ads126x::power_down] only stops the ADCs and turns off the internal reference and VBIAS.
+pub struct PwdnNoConnection;
@@ -0,0 +1,682 @@
+//! Register metadata in datasheet terms
+//!
+//! [REGISTERS] describes every register with its name, address, reset value and fields. It is used
+//! for the [Display](core::fmt::Display) implementations of the registers and [RegisterMap], and by
+//! [RegisterMap::diff] to compare two snapshots field by field.
+
+use core::fmt;
+
+use crate::{device::Status, registers::*};
+
+/// A field within a register
+#[derive(Debug)]
+pub struct FieldInfo {
+    /// Field name as used in the datasheet
+    pub name: &'static str,
+    /// Most significant bit
+    pub msb: u8,
+    /// Least significant bit
+    pub lsb: u8,
+    /// Names of the field values, values without a name are shown as numbers
+    pub values: &'static [(u8, &'static str)],
+}
+
+impl FieldInfo {
+    /// Mask of the field within the register
+    pub fn mask(&self) -> u8 {
+        (0xFF >> (7 - (self.msb - self.lsb))) << self.lsb
+    }
+
+    /// Extract field value from register contents
+    pub fn get(&self, reg: u8) -> u8 {
+        (reg & self.mask()) >> self.lsb
+    }
+
+    /// Register contents with the field set to `value`
+    pub fn set(&self, reg: u8, value: u8) -> u8 {
+        (reg & !self.mask()) | ((value << self.lsb) & self.mask())
+    }
+
+    /// Name of a field value
+    pub fn value_name(&self, value: u8) -> Option<&'static str> {
+        self.values
+            .iter()
+            .find(|(v, _)| *v == value)
+            .map(|(_, name)| *name)
+    }
+
+    /// Field value by name (case insensitive)
+    pub fn value(&self, name: &str) -> Option<u8> {
+        self.values
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(v, _)| *v)
+    }
+
+    fn fmt_value(&self, reg: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.get(reg);
+        match self.value_name(value) {
+            Some(name) => write!(f, "{} = {}", self.name, name),
+            None => write!(f, "{} = {}", self.name, value),
+        }
+    }
+}
+
+/// A device register
+#[derive(Debug)]
+pub struct RegisterInfo {
+    /// Register name as used in the datasheet
+    pub name: &'static str,
+    /// Register address
+    pub address: u8,
+    /// Value after reset, the ID register has no fixed reset value
+    pub reset: u8,
+    /// Fields from least to most significant bit
+    pub fields: &'static [FieldInfo],
+}
+
+impl RegisterInfo {
+    /// Field by name (case insensitive)
+    pub fn field(&self, name: &str) -> Option<&'static FieldInfo> {
+        self.fields
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Register contents decoded into its fields
+    pub fn decode(&'static self, reg: u8) -> Decoded {
+        Decoded {
+            fields: self.fields,
+            reg,
+        }
+    }
+}
+
+/// Register contents formatted as `FIELD = value, ...`, see [RegisterInfo::decode]
+pub struct Decoded {
+    fields: &'static [FieldInfo],
+    reg: u8,
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            field.fmt_value(self.reg, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Register by name (case insensitive)
+pub fn register(name: &str) -> Option<&'static RegisterInfo> {
+    REGISTERS
+        .iter()
+        .find(|reg| reg.name.eq_ignore_ascii_case(name))
+}
+
+/// Register at `address`
+pub fn register_at(address: u8) -> Option<&'static RegisterInfo> {
+    REGISTERS.get(address as usize)
+}
+
+const fn field(
+    name: &'static str,
+    msb: u8,
+    lsb: u8,
+    values: &'static [(u8, &'static str)],
+) -> FieldInfo {
+    FieldInfo {
+        name,
+        msb,
+        lsb,
+        values,
+    }
+}
+
+const fn reg(
+    name: &'static str,
+    address: u8,
+    reset: u8,
+    fields: &'static [FieldInfo],
+) -> RegisterInfo {
+    RegisterInfo {
+        name,
+        address,
+        reset,
+        fields,
+    }
+}
+
+const OFF_ON: &[(u8, &str)] = &[(0, "off"), (1, "on")];
+const DISABLED_ENABLED: &[(u8, &str)] = &[(0, "disabled"), (1, "enabled")];
+const NO_YES: &[(u8, &str)] = &[(0, "no"), (1, "yes")];
+
+const INPUTS: &[(u8, &str)] = &[
+    (0, "AIN0"),
+    (1, "AIN1"),
+    (2, "AIN2"),
+    (3, "AIN3"),
+    (4, "AIN4"),
+    (5, "AIN5"),
+    (6, "AIN6"),
+    (7, "AIN7"),
+    (8, "AIN8"),
+    (9, "AIN9"),
+    (10, "AINCOM"),
+    (11, "temperature sensor"),
+    (12, "analog power supply"),
+    (13, "digital power supply"),
+    (14, "TDAC"),
+    (15, "float"),
+];
+
+const IDAC_PINS: &[(u8, &str)] = &[
+    (0, "AIN0"),
+    (1, "AIN1"),
+    (2, "AIN2"),
+    (3, "AIN3"),
+    (4, "AIN4"),
+    (5, "AIN5"),
+    (6, "AIN6"),
+    (7, "AIN7"),
+    (8, "AIN8"),
+    (9, "AIN9"),
+    (10, "AINCOM"),
+    (11, "no connection"),
+];
+
+const IDAC_CURRENTS: &[(u8, &str)] = &[
+    (0, "off"),
+    (1, "50 uA"),
+    (2, "100 uA"),
+    (3, "250 uA"),
+    (4, "500 uA"),
+    (5, "750 uA"),
+    (6, "1000 uA"),
+    (7, "1500 uA"),
+    (8, "2000 uA"),
+    (9, "2500 uA"),
+    (10, "3000 uA"),
+];
+
+/// TDAC output voltages with a 5 V analog supply
+const TDAC_LEVELS: &[(u8, &str)] = &[
+    (0b01001, "4.5 V"),
+    (0b01000, "3.5 V"),
+    (0b00111, "3 V"),
+    (0b00110, "2.75 V"),
+    (0b00101, "2.625 V"),
+    (0b00100, "2.5625 V"),
+    (0b00011, "2.53125 V"),
+    (0b00010, "2.515625 V"),
+    (0b00001, "2.5078125 V"),
+    (0b00000, "2.5 V"),
+    (0b10001, "2.4921875 V"),
+    (0b10010, "2.484375 V"),
+    (0b10011, "2.46875 V"),
+    (0b10100, "2.4375 V"),
+    (0b10101, "2.375 V"),
+    (0b10110, "2.25 V"),
+    (0b10111, "2 V"),
+    (0b11000, "1.5 V"),
+    (0b11001, "0.5 V"),
+];
+
+/// All registers, indexed by address
+pub const REGISTERS: [RegisterInfo; REGISTER_COUNT] = [
+    reg(
+        "ID",
+        ID,
+        0x00,
+        &[
+            field("REV_ID", 4, 0, &[]),
+            field("DEV_ID", 7, 5, &[(0, "ADS1262"), (1, "ADS1263")]),
+        ],
+    ),
+    reg(
+        "POWER",
+        POWER,
+        0x11,
+        &[
+            field("INTREF", 0, 0, OFF_ON),
+            field("VBIAS", 1, 1, DISABLED_ENABLED),
+            field("RESET", 4, 4, NO_YES),
+        ],
+    ),
+    reg(
+        "INTERFACE",
+        INTERFACE,
+        0x05,
+        &[
+            field("CRC", 1, 0, &[(0, "off"), (1, "checksum"), (2, "CRC")]),
+            field("STATUS", 2, 2, DISABLED_ENABLED),
+            field("TIMEOUT", 3, 3, DISABLED_ENABLED),
+        ],
+    ),
+    reg(
+        "MODE0",
+        MODE0,
+        0x00,
+        &[
+            field(
+                "DELAY",
+                3,
+                0,
+                &[
+                    (0, "0 s"),
+                    (1, "8.7 us"),
+                    (2, "17 us"),
+                    (3, "35 us"),
+                    (4, "69 us"),
+                    (5, "139 us"),
+                    (6, "278 us"),
+                    (7, "555 us"),
+                    (8, "1.1 ms"),
+                    (9, "2.2 ms"),
+                    (10, "4.4 ms"),
+                    (11, "8.8 ms"),
+                ],
+            ),
+            field(
+                "CHOP",
+                5,
+                4,
+                &[
+                    (0, "disabled"),
+                    (1, "input chop"),
+                    (2, "IDAC rotation"),
+                    (3, "input chop and IDAC rotation"),
+                ],
+            ),
+            field("RUNMODE", 6, 6, &[(0, "continuous"), (1, "pulse")]),
+            field("REFREV", 7, 7, &[(0, "normal"), (1, "reversed")]),
+        ],
+    ),
+    reg(
+        "MODE1",
+        MODE1,
+        0x80,
+        &[
+            field(
+                "SBMAG",
+                2,
+                0,
+                &[
+                    (0, "none"),
+                    (1, "0.5 uA"),
+                    (2, "2 uA"),
+                    (3, "10 uA"),
+                    (4, "50 uA"),
+                    (5, "200 uA"),
+                    (6, "10 MOhm"),
+                ],
+            ),
+            field("SBPOL", 3, 3, &[(0, "pull-up"), (1, "pull-down")]),
+            field("SBADC", 4, 4, &[(0, "ADC1"), (1, "ADC2")]),
+            field(
+                "FILTER",
+                7,
+                5,
+                &[
+                    (0, "sinc1"),
+                    (1, "sinc2"),
+                    (2, "sinc3"),
+                    (3, "sinc4"),
+                    (4, "FIR"),
+                ],
+            ),
+        ],
+    ),
+    reg(
+        "MODE2",
+        MODE2,
+        0x04,
+        &[
+            field(
+                "DR",
+                3,
+                0,
+                &[
+                    (0, "2.5 SPS"),
+                    (1, "5 SPS"),
+                    (2, "10 SPS"),
+                    (3, "16.6 SPS"),
+                    (4, "20 SPS"),
+                    (5, "50 SPS"),
+                    (6, "60 SPS"),
+                    (7, "100 SPS"),
+                    (8, "400 SPS"),
+                    (9, "1200 SPS"),
+                    (10, "2400 SPS"),
+                    (11, "4800 SPS"),
+                    (12, "7200 SPS"),
+                    (13, "14400 SPS"),
+                    (14, "19200 SPS"),
+                    (15, "38400 SPS"),
+                ],
+            ),
+            field(
+                "GAIN",
+                6,
+                4,
+                &[(0, "1"), (1, "2"), (2, "4"), (3, "8"), (4, "16"), (5, "32")],
+            ),
+            field("BYPASS", 7, 7, &[(0, "PGA enabled"), (1, "PGA bypassed")]),
+        ],
+    ),
+    reg(
+        "INPMUX",
+        INPMUX,
+        0x01,
+        &[field("MUXN", 3, 0, INPUTS), field("MUXP", 7, 4, INPUTS)],
+    ),
+    reg("OFCAL0", OFCAL0, 0x00, &[field("OFC", 7, 0, &[])]),
+    reg("OFCAL1", OFCAL0 + 1, 0x00, &[field("OFC", 7, 0, &[])]),
+    reg("OFCAL2", OFCAL0 + 2, 0x00, &[field("OFC", 7, 0, &[])]),
+    reg("FSCAL0", FSCAL0, 0x00, &[field("FSC", 7, 0, &[])]),
+    reg("FSCAL1", FSCAL0 + 1, 0x00, &[field("FSC", 7, 0, &[])]),
+    reg("FSCAL2", FSCAL0 + 2, 0x40, &[field("FSC", 7, 0, &[])]),
+    reg(
+        "IDACMUX",
+        IDACMUX,
+        0xBB,
+        &[
+            field("MUX1", 3, 0, IDAC_PINS),
+            field("MUX2", 7, 4, IDAC_PINS),
+        ],
+    ),
+    reg(
+        "IDACMAG",
+        IDACMAG,
+        0x00,
+        &[
+            field("MAG1", 3, 0, IDAC_CURRENTS),
+            field("MAG2", 7, 4, IDAC_CURRENTS),
+        ],
+    ),
+    reg(
+        "REFMUX",
+        REFMUX,
+        0x00,
+        &[
+            field(
+                "RMUXN",
+                2,
+                0,
+                &[
+                    (0, "internal 2.5 V reference N"),
+                    (1, "AIN1"),
+                    (2, "AIN3"),
+                    (3, "AIN5"),
+                    (4, "AVSS"),
+                ],
+            ),
+            field(
+                "RMUXP",
+                5,
+                3,
+                &[
+                    (0, "internal 2.5 V reference P"),
+                    (1, "AIN0"),
+                    (2, "AIN2"),
+                    (3, "AIN4"),
+                    (4, "AVDD"),
+                ],
+            ),
+        ],
+    ),
+    reg(
+        "TDACP",
+        TDACP,
+        0x00,
+        &[
+            field("MAGP", 4, 0, TDAC_LEVELS),
+            field("OUTP", 7, 7, &[(0, "no connection"), (1, "AIN6")]),
+        ],
+    ),
+    reg(
+        "TDACN",
+        TDACN,
+        0x00,
+        &[
+            field("MAGN", 4, 0, TDAC_LEVELS),
+            field("OUTN", 7, 7, &[(0, "no connection"), (1, "AIN7")]),
+        ],
+    ),
+    reg("GPIOCON", GPIOCON, 0x00, &[field("CON", 7, 0, &[])]),
+    reg("GPIODIR", GPIODIR, 0x00, &[field("DIR", 7, 0, &[])]),
+    reg("GPIODAT", GPIODAT, 0x00, &[field("DAT", 7, 0, &[])]),
+    reg(
+        "ADC2CFG",
+        ADC2CFG,
+        0x00,
+        &[
+            field(
+                "GAIN2",
+                2,
+                0,
+                &[
+                    (0, "1"),
+                    (1, "2"),
+                    (2, "4"),
+                    (3, "8"),
+                    (4, "16"),
+                    (5, "32"),
+                    (6, "64"),
+                    (7, "128"),
+                ],
+            ),
+            field(
+                "REF2",
+                5,
+                3,
+                &[
+                    (0, "internal 2.5 V reference"),
+                    (1, "AIN0-AIN1"),
+                    (2, "AIN2-AIN3"),
+                    (3, "AIN4-AIN5"),
+                    (4, "AVDD-AVSS"),
+                ],
+            ),
+            field(
+                "DR2",
+                7,
+                6,
+                &[
+                    (0, "10 SPS"),
+                    (1, "100 SPS"),
+                    (2, "400 SPS"),
+                    (3, "800 SPS"),
+                ],
+            ),
+        ],
+    ),
+    reg(
+        "ADC2MUX",
+        ADC2MUX,
+        0x01,
+        &[field("MUXN2", 3, 0, INPUTS), field("MUXP2", 7, 4, INPUTS)],
+    ),
+    reg("ADC2OFC0", ADC2OFC0, 0x00, &[field("OFC2", 7, 0, &[])]),
+    reg("ADC2OFC1", ADC2OFC0 + 1, 0x00, &[field("OFC2", 7, 0, &[])]),
+    reg("ADC2FSC0", ADC2FSC0, 0x00, &[field("FSC2", 7, 0, &[])]),
+    reg("ADC2FSC1", ADC2FSC0 + 1, 0x40, &[field("FSC2", 7, 0, &[])]),
+];
+
+/// Analog inputs shared with GPIO0..GPIO7
+pub const GPIO_PINS: [&str; 8] = [
+    "AIN3", "AIN4", "AIN5", "AIN6", "AIN7", "AIN8", "AIN9", "AINCOM",
+];
+
+/// Fields of the [Status] byte
+pub const STATUS_FIELDS: &[FieldInfo] = &[
+    field("RESET", 0, 0, NO_YES),
+    field("PGAD_ALM", 1, 1, NO_YES),
+    field("PGAH_ALM", 2, 2, NO_YES),
+    field("PGAL_ALM", 3, 3, NO_YES),
+    field("REF_ALM", 4, 4, NO_YES),
+    field("EXTCLK", 5, 5, NO_YES),
+    field("ADC1", 6, 6, &[(0, "old"), (1, "new")]),
+    field("ADC2", 7, 7, &[(0, "old"), (1, "new")]),
+];
+
+macro_rules! impl_display {
+    ($($typ:ident = $addr:ident),* $(,)?) => {
+        $(
+            impl fmt::Display for $typ {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    REGISTERS[$addr as usize].decode(self.0).fmt(f)
+                }
+            }
+        )*
+    };
+}
+
+impl_display!(
+    Id = ID,
+    Power = POWER,
+    Interface = INTERFACE,
+    Mode0 = MODE0,
+    Mode1 = MODE1,
+    Mode2 = MODE2,
+    InpMux = INPMUX,
+    IdacMux = IDACMUX,
+    IdacMag = IDACMAG,
+    RefMux = REFMUX,
+    TdacP = TDACP,
+    TdacN = TDACN,
+    GpioCon = GPIOCON,
+    GpioDir = GPIODIR,
+    GpioDat = GPIODAT,
+    Adc2Cfg = ADC2CFG,
+    Adc2Mux = ADC2MUX,
+);
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Decoded {
+            fields: STATUS_FIELDS,
+            reg: self.0,
+        }
+        .fmt(f)
+    }
+}
+
+/// One register per line, calibration registers are summarized as [Calibration]
+impl fmt::Display for RegisterMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (info, reg) in REGISTERS.iter().zip(self.0.iter()) {
+            let addr = info.address;
+            if (OFCAL0..IDACMUX).contains(&addr) || addr >= ADC2OFC0 {
+                continue;
+            }
+            writeln!(
+                f,
+                "{:<9} ({:02X}h) = {:02X}h: {}",
+                info.name,
+                info.address,
+                reg,
+                info.decode(*reg)
+            )?;
+        }
+        let cal = self.calibration();
+        write!(
+            f,
+            "Calibration: OFCAL1 = {}, FSCAL1 = {:#08x}, OFCAL2 = {}, FSCAL2 = {:#06x}",
+            cal.ofcal1, cal.fscal1, cal.ofcal2, cal.fscal2
+        )
+    }
+}
+
+/// A field that differs between two register snapshots, see [RegisterMap::diff]
+#[derive(Debug)]
+pub struct FieldDiff {
+    /// Register containing the field
+    pub register: &'static RegisterInfo,
+    /// The field
+    pub field: &'static FieldInfo,
+    /// Value in the first snapshot
+    pub a: u8,
+    /// Value in the second snapshot
+    pub b: u8,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}: ", self.register.name, self.field.name)?;
+        match self.field.value_name(self.a) {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "{}", self.a)?,
+        }
+        f.write_str(" -> ")?;
+        match self.field.value_name(self.b) {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "{}", self.b),
+        }
+    }
+}
+
+impl RegisterMap {
+    /// Compare with another snapshot field by field, yielding every field that differs
+    pub fn diff<'a>(&'a self, other: &'a RegisterMap) -> impl Iterator<Item = FieldDiff> + 'a {
+        REGISTERS
+            .iter()
+            .zip(self.0.iter().zip(other.0.iter()))
+            .flat_map(|(register, (a, b))| {
+                register.fields.iter().filter_map(move |field| {
+                    let (a, b) = (field.get(*a), field.get(*b));
+                    (a != b).then_some(FieldDiff {
+                        register,
+                        field,
+                        a,
+                        b,
+                    })
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{format, string::ToString, vec::Vec};
+
+    use super::{register, REGISTERS};
+    use crate::registers::{Mode2, Mode2Dr, Mode2Gain, RegisterMap};
+
+    #[test]
+    fn test_table() {
+        let defaults = RegisterMap::default();
+        for (addr, info) in REGISTERS.iter().enumerate() {
+            assert_eq!(info.address as usize, addr, "{}", info.name);
+            if addr > 0 {
+                assert_eq!(info.reset, defaults.0[addr], "{}", info.name);
+            }
+        }
+
+        let gain = register("mode2").unwrap().field("gain").unwrap();
+        assert_eq!(gain.mask(), 0x70);
+        assert_eq!(gain.get(0x38), 3);
+        assert_eq!(gain.set(0x38, gain.value("16").unwrap()), 0x48);
+    }
+
+    #[test]
+    fn test_display_and_diff() {
+        let mode2 = Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false);
+        assert_eq!(
+            mode2.to_string(),
+            "DR = 400 SPS, GAIN = 8, BYPASS = PGA enabled"
+        );
+
+        let a = RegisterMap::default();
+        let mut b = a;
+        b.set(mode2);
+        let diff: Vec<_> = a.diff(&b).map(|d| d.to_string()).collect();
+        assert_eq!(diff, ["MODE2.DR: 20 SPS -> 400 SPS", "MODE2.GAIN: 1 -> 8"]);
+        assert!(format!("{b}").contains("MODE2     (05h) = 38h: DR = 400 SPS"));
+    }
+}
@@ -0,0 +1,441 @@
+//! Software post-processing of conversion results
+//!
+//! Stages implement [Stage] and process a stream of integer values: ADC codes with
+//! [Stage::push_data], or voltages e.g. from [Data::to_nanovolts] with [Stage::push]. Stages are
+//! combined with [Stage::then], [PerChannel] keeps separate state for each channel of a scan.
+//! Decimating stages only return a value for every Nth input.
+//!
+//! ```
+//! use ads126x::processing::{Median, SpikeReject, Stage, Boxcar};
+//!
+//! // Reject steps above 1000 codes, then median of 3 and a moving average over 4 medians
+//! let mut chain = SpikeReject::new(1000, 2).then(Median::<3>::new()).then(Boxcar::<4>::new());
+//! let out: Vec<_> = [10, 12, 5000, 11, 9, 10, 13].iter().filter_map(|x| chain.push(*x)).collect();
+//! assert_eq!(out, [11, 11]);
+//! ```
+
+use crate::{capture::Sample, device::Data};
+
+/// Divide rounding half away from zero
+pub(crate) fn div_round(n: i128, d: i128) -> i64 {
+    let q = (n.abs() + d.abs() / 2) / d.abs();
+    (if (n < 0) != (d < 0) { -q } else { q }) as i64
+}
+
+/// A processing stage
+pub trait Stage {
+    /// Process one value, returns the output if one is ready
+    fn push(&mut self, x: i64) -> Option<i64>;
+
+    /// Forget all previous values
+    fn reset(&mut self);
+
+    /// Process the code of a conversion, keeping its status, reference and gain
+    fn push_data(&mut self, data: Data) -> Option<Data> {
+        self.push(data.code.into()).map(|code| Data {
+            code: code.clamp(i32::MIN.into(), i32::MAX.into()) as i32,
+            ..data
+        })
+    }
+
+    /// Feed the output of this stage into `next`
+    fn then<S: Stage>(self, next: S) -> Chain<Self, S>
+    where
+        Self: Sized,
+    {
+        Chain(self, next)
+    }
+}
+
+/// Two stages in series, see [Stage::then]
+#[derive(Debug, Clone)]
+pub struct Chain<A, B>(pub A, pub B);
+
+impl<A: Stage, B: Stage> Stage for Chain<A, B> {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        self.0.push(x).and_then(|y| self.1.push(y))
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+        self.1.reset();
+    }
+}
+
+/// Mean of all values since the last reset
+#[derive(Debug, Clone, Default)]
+pub struct RunningMean {
+    sum: i128,
+    count: u64,
+}
+
+impl RunningMean {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values averaged
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl Stage for RunningMean {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        self.sum += i128::from(x);
+        self.count += 1;
+        Some(div_round(self.sum, self.count.into()))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Moving average over the last `N` values
+#[derive(Debug, Clone)]
+pub struct Boxcar<const N: usize> {
+    values: [i64; N],
+    pos: usize,
+    len: usize,
+    sum: i128,
+}
+
+impl<const N: usize> Boxcar<N> {
+    pub fn new() -> Self {
+        Self {
+            values: [0; N],
+            pos: 0,
+            len: 0,
+            sum: 0,
+        }
+    }
+}
+
+impl<const N: usize> Default for Boxcar<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Stage for Boxcar<N> {
+    /// Returns the average of the values so far until `N` values are collected
+    fn push(&mut self, x: i64) -> Option<i64> {
+        if N == 0 {
+            return Some(x);
+        }
+        if self.len == N {
+            self.sum -= i128::from(self.values[self.pos]);
+        } else {
+            self.len += 1;
+        }
+        self.values[self.pos] = x;
+        self.sum += i128::from(x);
+        self.pos = (self.pos + 1) % N;
+        Some(div_round(self.sum, self.len as i128))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Exponential moving average `y += (x - y) / 2^shift`
+#[derive(Debug, Clone)]
+pub struct Ema {
+    shift: u32,
+    /// Average scaled by 2^shift
+    acc: Option<i128>,
+}
+
+impl Ema {
+    /// Time constant of about `2^shift` values, the first value initializes the average
+    pub fn new(shift: u32) -> Self {
+        Self {
+            shift: shift.min(62),
+            acc: None,
+        }
+    }
+}
+
+impl Stage for Ema {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        let x = i128::from(x) << self.shift;
+        let acc = match self.acc {
+            Some(acc) => acc + ((x - acc) >> self.shift),
+            None => x,
+        };
+        self.acc = Some(acc);
+        Some(div_round(acc, 1 << self.shift))
+    }
+
+    fn reset(&mut self) {
+        self.acc = None;
+    }
+}
+
+/// Median of each block of `N` values, decimates by `N`
+///
+/// With an even `N` the mean of the two middle values is returned.
+#[derive(Debug, Clone)]
+pub struct Median<const N: usize> {
+    values: [i64; N],
+    len: usize,
+}
+
+impl<const N: usize> Median<N> {
+    pub fn new() -> Self {
+        Self {
+            values: [0; N],
+            len: 0,
+        }
+    }
+}
+
+impl<const N: usize> Default for Median<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Stage for Median<N> {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        if N == 0 {
+            return Some(x);
+        }
+        self.values[self.len] = x;
+        self.len += 1;
+        if self.len < N {
+            return None;
+        }
+        self.len = 0;
+        self.values.sort_unstable();
+        if N % 2 == 1 {
+            Some(self.values[N / 2])
+        } else {
+            let sum = i128::from(self.values[N / 2 - 1]) + i128::from(self.values[N / 2]);
+            Some(div_round(sum, 2))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Drops values differing from the last accepted value by more than a threshold
+///
+/// After `max_rejects` consecutive rejections the next value is accepted, so that a real step
+/// in the input is followed.
+#[derive(Debug, Clone)]
+pub struct SpikeReject {
+    threshold: u64,
+    max_rejects: u32,
+    last: Option<i64>,
+    rejects: u32,
+}
+
+impl SpikeReject {
+    pub fn new(threshold: u64, max_rejects: u32) -> Self {
+        Self {
+            threshold,
+            max_rejects,
+            last: None,
+            rejects: 0,
+        }
+    }
+
+    /// Number of values rejected since the last accepted value
+    pub fn rejects(&self) -> u32 {
+        self.rejects
+    }
+}
+
+impl Stage for SpikeReject {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        if let Some(last) = self.last {
+            if last.abs_diff(x) > self.threshold && self.rejects < self.max_rejects {
+                self.rejects += 1;
+                return None;
+            }
+        }
+        self.last = Some(x);
+        self.rejects = 0;
+        Some(x)
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+        self.rejects = 0;
+    }
+}
+
+/// CIC decimator of order `ORDER` decimating by `ratio`, normalized to unity gain at DC
+///
+/// Uses wrapping integer arithmetic, `ratio^ORDER` times the largest input must fit into an i64.
+#[derive(Debug, Clone)]
+pub struct Cic<const ORDER: usize> {
+    ratio: u32,
+    integrators: [i64; ORDER],
+    combs: [i64; ORDER],
+    count: u32,
+}
+
+impl<const ORDER: usize> Cic<ORDER> {
+    pub fn new(ratio: u32) -> Self {
+        Self {
+            ratio: ratio.max(1),
+            integrators: [0; ORDER],
+            combs: [0; ORDER],
+            count: 0,
+        }
+    }
+
+    /// DC gain before normalization, `ratio^ORDER`
+    pub fn gain(&self) -> i64 {
+        (0..ORDER).fold(1, |g: i64, _| g.wrapping_mul(self.ratio.into()))
+    }
+}
+
+impl<const ORDER: usize> Stage for Cic<ORDER> {
+    fn push(&mut self, x: i64) -> Option<i64> {
+        let mut y = x;
+        for i in self.integrators.iter_mut() {
+            *i = i.wrapping_add(y);
+            y = *i;
+        }
+        self.count += 1;
+        if self.count < self.ratio {
+            return None;
+        }
+        self.count = 0;
+        for c in self.combs.iter_mut() {
+            let prev = *c;
+            *c = y;
+            y = y.wrapping_sub(prev);
+        }
+        Some(div_round(y.into(), self.gain().into()))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.ratio);
+    }
+}
+
+/// Separate copies of a stage for `N` channels
+#[derive(Debug, Clone)]
+pub struct PerChannel<S, const N: usize> {
+    stages: [S; N],
+}
+
+impl<S: Stage + Clone, const N: usize> PerChannel<S, N> {
+    pub fn new(stage: S) -> Self {
+        Self {
+            stages: core::array::from_fn(|_| stage.clone()),
+        }
+    }
+
+    /// Stage of `channel`
+    pub fn channel(&mut self, channel: usize) -> Option<&mut S> {
+        self.stages.get_mut(channel)
+    }
+
+    /// Process one value of `channel`, values of unknown channels are dropped
+    pub fn push(&mut self, channel: usize, x: i64) -> Option<i64> {
+        self.stages.get_mut(channel)?.push(x)
+    }
+
+    /// Process the code of a channel-tagged sample, the timestamp of the output is that of
+    /// the last input
+    pub fn push_sample(&mut self, sample: &Sample) -> Option<Sample> {
+        let data = self
+            .stages
+            .get_mut(usize::from(sample.channel))?
+            .push_data(sample.data)?;
+        Some(Sample { data, ..*sample })
+    }
+
+    /// Reset all channels
+    pub fn reset(&mut self) {
+        self.stages.iter_mut().for_each(Stage::reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Status;
+
+    fn run<S: Stage>(stage: &mut S, xs: &[i64], out: &mut [i64]) -> usize {
+        let mut n = 0;
+        for x in xs {
+            if let Some(y) = stage.push(*x) {
+                out[n] = y;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    #[test]
+    fn test_div_round() {
+        assert_eq!(div_round(7, 2), 4);
+        assert_eq!(div_round(-7, 2), -4);
+        assert_eq!(div_round(7, -2), -4);
+        assert_eq!(div_round(-7, -2), 4);
+        assert_eq!(div_round(5, -3), -2);
+        assert_eq!(div_round(4, -3), -1);
+    }
+
+    #[test]
+    fn test_stages() {
+        let mut out = [0; 8];
+        let xs = [1, 2, 3, 4, 5, 6];
+
+        assert_eq!(run(&mut RunningMean::new(), &xs, &mut out), 6);
+        assert_eq!(out[..6], [1, 2, 2, 3, 3, 4]);
+        assert_eq!(run(&mut Boxcar::<2>::new(), &xs, &mut out), 6);
+        assert_eq!(out[..6], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            run(&mut Median::<3>::new(), &[5, -1, 3, 9, 9, 0], &mut out),
+            2
+        );
+        assert_eq!(out[..2], [3, 9]);
+        assert_eq!(
+            run(
+                &mut SpikeReject::new(10, 1),
+                &[0, 100, 5, 100, 101],
+                &mut out
+            ),
+            3
+        );
+        assert_eq!(out[..3], [0, 5, 101]);
+
+        let mut ema = Ema::new(2);
+        assert_eq!(run(&mut ema, &[0, 100, 100, 100], &mut out), 4);
+        assert_eq!(out[..4], [0, 25, 44, 58]);
+
+        // Constant input passes a normalized CIC unchanged
+        let mut cic = Cic::<3>::new(4);
+        assert_eq!(cic.gain(), 64);
+        assert_eq!(run(&mut cic, &[-1000; 16], &mut out), 4);
+        assert_eq!(out[3], -1000);
+
+        let mut chain = Boxcar::<2>::new().then(Median::<2>::new());
+        assert_eq!(run(&mut chain, &xs, &mut out), 3);
+        chain.reset();
+        assert_eq!(chain.push(10), None);
+    }
+
+    #[test]
+    fn test_per_channel() {
+        let mut mean = PerChannel::<_, 2>::new(RunningMean::new());
+        let sample =
+            |channel, code| Sample::new(channel, 0, Data::new(Status(0), code, 2_500_000, 1));
+        mean.push_sample(&sample(0, 10));
+        mean.push_sample(&sample(1, 100));
+        assert_eq!(mean.push_sample(&sample(0, 20)).unwrap().data.code, 15);
+        assert_eq!(mean.push_sample(&sample(1, 200)).unwrap().data.code, 150);
+        assert!(mean.push_sample(&sample(2, 0)).is_none());
+    }
+}
@@ -30,6 +30,101 @@ pub(crate) const ADC2OFC0: u8 = 0x17;
 pub(crate) const ADC2FSC0: u8 = 0x19;
 //pub(crate) const ADC2FSC1: u8 = 0x1A;
 
+/// Number of registers in the register map
+pub const REGISTER_COUNT: usize = 0x1B;
+
+/// Contents of all device registers, defaults to the reset values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RegisterMap(pub [u8; REGISTER_COUNT]);
+
+impl Default for RegisterMap {
+    fn default() -> Self {
+        Self([
+            0x00, 0x11, 0x05, 0x00, 0x80, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0xBB,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x40,
+        ])
+    }
+}
+
+impl RegisterMap {
+    pub fn get<REG: Register>(&self) -> REG {
+        REG::from_byte(self.0[REG::REG as usize])
+    }
+
+    pub fn set<REG: Register>(&mut self, reg: REG) {
+        self.0[REG::REG as usize] = reg.into_byte();
+    }
+
+    /// Calibration registers
+    pub fn calibration(&self) -> Calibration {
+        Calibration::from_bytes(
+            self.0[OFCAL0 as usize..OFCAL0 as usize + 6]
+                .try_into()
+                .unwrap(),
+            self.0[ADC2OFC0 as usize..ADC2OFC0 as usize + 4]
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    /// Set calibration registers
+    pub fn set_calibration(&mut self, cal: &Calibration) {
+        let (adc1, adc2) = cal.to_bytes();
+        self.0[OFCAL0 as usize..OFCAL0 as usize + 6].copy_from_slice(&adc1);
+        self.0[ADC2OFC0 as usize..ADC2OFC0 as usize + 4].copy_from_slice(&adc2);
+    }
+}
+
+/// Offset and full-scale calibration of ADC1 and ADC2
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    /// ADC1 offset calibration (OFCAL, 24-bit signed)
+    pub ofcal1: i32,
+    /// ADC1 full-scale calibration (FSCAL, 24-bit unsigned)
+    pub fscal1: u32,
+    /// ADC2 offset calibration (ADC2OFC)
+    pub ofcal2: i16,
+    /// ADC2 full-scale calibration (ADC2FSC)
+    pub fscal2: u16,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            ofcal1: 0,
+            fscal1: 0x400000,
+            ofcal2: 0,
+            fscal2: 0x4000,
+        }
+    }
+}
+
+impl Calibration {
+    /// Decode OFCAL0..FSCAL2 and ADC2OFC0..ADC2FSC1 register contents
+    pub fn from_bytes(adc1: [u8; 6], adc2: [u8; 4]) -> Self {
+        Self {
+            ofcal1: i32::from_le_bytes([0, adc1[0], adc1[1], adc1[2]]) >> 8,
+            fscal1: u32::from_le_bytes([adc1[3], adc1[4], adc1[5], 0]),
+            ofcal2: i16::from_le_bytes([adc2[0], adc2[1]]),
+            fscal2: u16::from_le_bytes([adc2[2], adc2[3]]),
+        }
+    }
+
+    /// Encode into OFCAL0..FSCAL2 and ADC2OFC0..ADC2FSC1 register contents
+    pub fn to_bytes(&self) -> ([u8; 6], [u8; 4]) {
+        let o1 = self.ofcal1.to_le_bytes();
+        let f1 = self.fscal1.to_le_bytes();
+        let o2 = self.ofcal2.to_le_bytes();
+        let f2 = self.fscal2.to_le_bytes();
+        (
+            [o1[0], o1[1], o1[2], f1[0], f1[1], f1[2]],
+            [o2[0], o2[1], f2[0], f2[1]],
+        )
+    }
+}
+
 pub trait Register {
     const REG: u8;
 
@@ -62,6 +157,9 @@ macro_rules! impl_register {
     };
 }
 
+/// Device revisions ([Id::rev_id]) accepted when probing a device
+pub const SUPPORTED_REVISIONS: &[u8] = &[1, 2, 3];
+
 bitfield! {
     pub struct Id(u8);
     impl Debug;
@@ -70,8 +168,25 @@ bitfield! {
     pub dev_id, _: 7, 5;
 }
 impl_register!(Id, ID = 0);
+impl Id {
+    pub fn device(&self) -> Option<IdDev> {
+        match self.dev_id() {
+            0 => Some(IdDev::Ads1262),
+            1 => Some(IdDev::Ads1263),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IdDev {
+    Ads1262 = 0,
+    Ads1263 = 1,
+}
 
 bitfield! {
+    #[derive(Copy, Clone)]
     pub struct Power(u8);
     impl Debug;
     // The fields default to u16
@@ -149,6 +264,7 @@ impl Mode0 {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Mode1Filter {
     Sinc1 = 0,
     Sinc2 = 1,
@@ -157,6 +273,7 @@ pub enum Mode1Filter {
     Fir = 4,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Mode1SBMag {
     None = 0,
     Ua0_5 = 1,
@@ -167,6 +284,15 @@ pub enum Mode1SBMag {
     Res = 6,
 }
 
+impl Mode1SBMag {
+    /// Sensor bias current in nA, `None` for the 10 MOhm bias resistor
+    pub fn nanoamps(&self) -> Option<u32> {
+        let mut mode1 = Mode1(0);
+        mode1.set_sbmag(*self as u8);
+        mode1.sensor_bias_na()
+    }
+}
+
 bitfield! {
     pub struct Mode1(u8);
     impl Debug;
@@ -178,6 +304,19 @@ bitfield! {
 }
 impl_register!(Mode1, MODE1 = 0x80);
 impl Mode1 {
+    /// Sensor bias current in nA, `None` for the 10 MOhm bias resistor or a reserved value
+    pub fn sensor_bias_na(&self) -> Option<u32> {
+        match self.sbmag() {
+            0 => Some(0),
+            1 => Some(500),
+            2 => Some(2_000),
+            3 => Some(10_000),
+            4 => Some(50_000),
+            5 => Some(200_000),
+            _ => None,
+        }
+    }
+
     pub fn with(sbmag: Mode1SBMag, sbpol: bool, sbadc: bool, filter: Mode1Filter) -> Self {
         let mut this = Self(0);
         this.set_sbmag(sbmag as u8);
@@ -188,6 +327,7 @@ impl Mode1 {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Mode2Gain {
     None = 0,
     Gain2 = 1,
@@ -198,18 +338,19 @@ pub enum Mode2Gain {
 }
 
 impl Mode2Gain {
-    pub fn gain(&self) -> f32 {
+    pub fn gain(&self) -> u8 {
         match self {
-            Mode2Gain::None => 1.0,
-            Mode2Gain::Gain2 => 2.0,
-            Mode2Gain::Gain4 => 4.0,
-            Mode2Gain::Gain8 => 8.0,
-            Mode2Gain::Gain16 => 16.0,
-            Mode2Gain::Gain32 => 32.0,
+            Mode2Gain::None => 1,
+            Mode2Gain::Gain2 => 2,
+            Mode2Gain::Gain4 => 4,
+            Mode2Gain::Gain8 => 8,
+            Mode2Gain::Gain16 => 16,
+            Mode2Gain::Gain32 => 32,
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Mode2Dr {
     Sps2_5 = 0,
     Sps5 = 1,
@@ -229,6 +370,30 @@ pub enum Mode2Dr {
     Sps38400 = 15,
 }
 
+impl Mode2Dr {
+    /// Conversion period in microseconds (rounded), [Mode2Dr::Sps16_6] is 50/3 SPS (60 ms)
+    pub fn period_us(&self) -> u32 {
+        match self {
+            Mode2Dr::Sps2_5 => 400_000,
+            Mode2Dr::Sps5 => 200_000,
+            Mode2Dr::Sps10 => 100_000,
+            Mode2Dr::Sps16_6 => 60_000,
+            Mode2Dr::Sps20 => 50_000,
+            Mode2Dr::Sps50 => 20_000,
+            Mode2Dr::Sps60 => 16_667,
+            Mode2Dr::Sps100 => 10_000,
+            Mode2Dr::Sps400 => 2_500,
+            Mode2Dr::Sps1200 => 833,
+            Mode2Dr::Sps2400 => 417,
+            Mode2Dr::Sps4800 => 208,
+            Mode2Dr::Sps7200 => 139,
+            Mode2Dr::Sps14400 => 69,
+            Mode2Dr::Sps19200 => 52,
+            Mode2Dr::Sps38400 => 26,
+        }
+    }
+}
+
 bitfield! {
     pub struct Mode2(u8);
     impl Debug;
@@ -246,8 +411,18 @@ impl Mode2 {
         this.set_bypass(bypass);
         this
     }
+
+    /// Effective PGA gain, 1 if the PGA is bypassed
+    pub fn pga_gain(&self) -> u8 {
+        if self.bypass() {
+            1
+        } else {
+            1 << self.gain().min(5)
+        }
+    }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum InpMuxMuxx {
     Ain0 = 0,
     Ain1 = 1,
@@ -345,6 +520,31 @@ pub enum IdacMagMagx {
     Ua3000 = 10,
 }
 
+impl IdacMagMagx {
+    /// Current in uA
+    pub fn microamps(&self) -> u32 {
+        match self {
+            IdacMagMagx::Off => 0,
+            IdacMagMagx::Ua50 => 50,
+            IdacMagMagx::Ua100 => 100,
+            IdacMagMagx::Ua250 => 250,
+            IdacMagMagx::Ua500 => 500,
+            IdacMagMagx::Ua750 => 750,
+            IdacMagMagx::Ua1000 => 1000,
+            IdacMagMagx::Ua1500 => 1500,
+            IdacMagMagx::Ua2000 => 2000,
+            IdacMagMagx::Ua2500 => 2500,
+            IdacMagMagx::Ua3000 => 3000,
+        }
+    }
+}
+
+/// IDAC magnitude field value to current in uA
+fn idac_ua(mag: u8) -> Option<u32> {
+    const UA: [u32; 11] = [0, 50, 100, 250, 500, 750, 1000, 1500, 2000, 2500, 3000];
+    UA.get(mag as usize).copied()
+}
+
 bitfield! {
     pub struct IdacMag(u8);
     impl Debug;
@@ -353,6 +553,17 @@ bitfield! {
     pub mag2, _ : 7, 4;
 }
 impl_register!(IdacMag, IDACMAG = 0x00);
+impl IdacMag {
+    /// IDAC1 current in uA, `None` for reserved values
+    pub fn idac1_ua(&self) -> Option<u32> {
+        idac_ua(self.mag1())
+    }
+
+    /// IDAC2 current in uA, `None` for reserved values
+    pub fn idac2_ua(&self) -> Option<u32> {
+        idac_ua(self.mag2())
+    }
+}
 
 pub enum RefMuxMuxP {
     InternalRefP = 0,
@@ -364,9 +575,9 @@ pub enum RefMuxMuxP {
 
 pub enum RefMuxMuxN {
     InternalRefN = 0,
-    Ain0 = 1,
-    Ain2 = 2,
-    Ain4 = 3,
+    Ain1 = 1,
+    Ain3 = 2,
+    Ain5 = 3,
     Avss = 4,
 }
 
@@ -374,10 +585,18 @@ bitfield! {
     pub struct RefMux(u8);
     impl Debug;
     // The fields default to u16
-    pub rmuxn, _: 2, 0;
-    pub rmuxp, _ : 5, 3;
+    pub rmuxn, set_rmuxn: 2, 0;
+    pub rmuxp, set_rmuxp: 5, 3;
 }
 impl_register!(RefMux, REFMUX = 0x00);
+impl RefMux {
+    pub fn with(p: RefMuxMuxP, n: RefMuxMuxN) -> Self {
+        let mut this = Self(0);
+        this.set_rmuxp(p as u8);
+        this.set_rmuxn(n as u8);
+        this
+    }
+}
 
 bitfield! {
     pub struct TdacP(u8);
@@ -463,6 +682,7 @@ pub enum Adc2CfgRef {
     AvddAvss = 4,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Adc2CfgGain {
     None = 0,
     Gain2 = 1,
@@ -475,16 +695,16 @@ pub enum Adc2CfgGain {
 }
 
 impl Adc2CfgGain {
-    pub fn gain(&self) -> f32 {
+    pub fn gain(&self) -> u8 {
         match self {
-            Adc2CfgGain::None => 1.0,
-            Adc2CfgGain::Gain2 => 2.0,
-            Adc2CfgGain::Gain4 => 4.0,
-            Adc2CfgGain::Gain8 => 8.0,
-            Adc2CfgGain::Gain16 => 16.0,
-            Adc2CfgGain::Gain32 => 32.0,
-            Adc2CfgGain::Gain64 => 64.0,
-            Adc2CfgGain::Gain128 => 128.0,
+            Adc2CfgGain::None => 1,
+            Adc2CfgGain::Gain2 => 2,
+            Adc2CfgGain::Gain4 => 4,
+            Adc2CfgGain::Gain8 => 8,
+            Adc2CfgGain::Gain16 => 16,
+            Adc2CfgGain::Gain32 => 32,
+            Adc2CfgGain::Gain64 => 64,
+            Adc2CfgGain::Gain128 => 128,
         }
     }
 }
@@ -493,17 +713,122 @@ bitfield! {
     pub struct Adc2Cfg(u8);
     impl Debug;
     // The fields default to u16
-    pub gain2, _: 2, 0;
-    pub ref2, _ : 5, 3;
-    pub dr2, _ : 7, 6;
+    pub gain2, set_gain2: 2, 0;
+    pub ref2, set_ref2: 5, 3;
+    pub dr2, set_dr2: 7, 6;
 }
 impl_register!(Adc2Cfg, ADC2CFG = 0x00);
+impl Adc2Cfg {
+    pub fn with(dr: Adc2CfgDr, reference: Adc2CfgRef, gain: Adc2CfgGain) -> Self {
+        let mut this = Self(0);
+        this.set_dr2(dr as u8);
+        this.set_ref2(reference as u8);
+        this.set_gain2(gain as u8);
+        this
+    }
+
+    /// Effective PGA gain
+    pub fn pga_gain(&self) -> u8 {
+        1 << self.gain2()
+    }
+}
 
 bitfield! {
     pub struct Adc2Mux(u8);
     impl Debug;
     // The fields default to u16
-    pub muxn2, _: 3, 0;
-    pub muxp2, _ : 7, 4;
+    pub muxn2, set_muxn2: 3, 0;
+    pub muxp2, set_muxp2: 7, 4;
 }
 impl_register!(Adc2Mux, ADC2MUX = 0x01);
+impl Adc2Mux {
+    pub fn differential(inp: InpMuxMuxx, inn: InpMuxMuxx) -> Self {
+        let mut this = Self(0);
+        this.set_muxn2(inn as u8);
+        this.set_muxp2(inp as u8);
+        this
+    }
+
+    pub fn singleended(inp: InpMuxMuxx) -> Self {
+        Self::differential(inp, InpMuxMuxx::AinCom)
+    }
+}
+
+/// Invoke `$m!(Register { field: type, ... })` for every register and the [Status](crate::device::Status) byte.
+///
+/// Used to generate trait impls that need the decoded fields of each register.
+#[allow(unused_macros)]
+macro_rules! for_each_register {
+    ($m:ident) => {
+        $m!(Id {
+            rev_id: u8,
+            dev_id: u8
+        });
+        $m!(Power {
+            intref: bool,
+            vbias: bool,
+            reset: bool
+        });
+        $m!(Interface {
+            crc: u8,
+            status: bool,
+            time_out: bool
+        });
+        $m!(Mode0 {
+            delay: u8,
+            chop: u8,
+            run_mode: bool,
+            refrev: bool
+        });
+        $m!(Mode1 {
+            sbmag: u8,
+            sbpol: bool,
+            sbadc: bool,
+            filter: u8
+        });
+        $m!(Mode2 {
+            dr: u8,
+            gain: u8,
+            bypass: bool
+        });
+        $m!(InpMux { muxn: u8, muxp: u8 });
+        $m!(IdacMux { mux1: u8, mux2: u8 });
+        $m!(IdacMag { mag1: u8, mag2: u8 });
+        $m!(RefMux {
+            rmuxn: u8,
+            rmuxp: u8
+        });
+        $m!(TdacP {
+            magp: u8,
+            outp: bool
+        });
+        $m!(TdacN {
+            magn: u8,
+            outn: bool
+        });
+        $m!(GpioCon { con: u8 });
+        $m!(GpioDir { dir: u8 });
+        $m!(GpioDat { dat: u8 });
+        $m!(Adc2Cfg {
+            gain2: u8,
+            ref2: u8,
+            dr2: u8
+        });
+        $m!(Adc2Mux {
+            muxn2: u8,
+            muxp2: u8
+        });
+        $m!(Status {
+            reset: bool,
+            pgad_alm: bool,
+            pgah_alm: bool,
+            pgal_alm: bool,
+            ref_alm: bool,
+            extclk: bool,
+            adc1: bool,
+            adc2: bool
+        });
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use for_each_register;
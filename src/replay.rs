@@ -0,0 +1,615 @@
+//! Replaying recorded sessions through the driver
+//!
+//! [Replay] is a virtual device implementing the SPI and pin traits which serves recorded data in
+//! order. It replays either
+//! * a raw SPI transaction log written by [Recorder]: every transaction must send the same bytes
+//!   as during recording and receives the recorded response, or
+//! * a binary [capture](crate::capture): register accesses are served from the register snapshot in
+//!   the header and every data read receives the next sample, framed according to the current
+//!   INTERFACE register. The PGA gain configured for the ADC read must match the recorded gain.
+//!
+//! A deviation from the recording is returned as the SPI error of the transaction and kept for
+//! [Replay::finish], which also fails if recorded transactions or samples were not consumed.
+//! Conversion timing is not replayed, DRDY always reports new data.
+//!
+//! The transaction log starts with the magic `ADST` and a version byte (1), followed by one entry
+//! per transaction: the length as u16 little endian, the bytes sent and the bytes received.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use ads126x::{replay::{Recorder, Replay}, sim::SimAds126x, Ads1263};
+//!
+//! // Record a session
+//! let sim = SimAds126x::ads1263();
+//! let mut log = Vec::new();
+//! let spi = Recorder::new(sim.spi(), |b: &[u8]| {
+//!     log.extend_from_slice(b);
+//!     Ok::<(), core::convert::Infallible>(())
+//! });
+//! let mut adc = Ads1263::probe(spi, sim.cs(), sim.drdy(), sim.rst()).unwrap();
+//! adc.start1().unwrap();
+//! let recorded = adc.read_adc1().unwrap();
+//! drop(adc);
+//!
+//! // Replay it
+//! let replay = Replay::from_log(&log).unwrap();
+//! let mut adc = Ads1263::probe(replay.spi(), replay.cs(), replay.drdy(), replay.rst()).unwrap();
+//! adc.start1().unwrap();
+//! assert_eq!(adc.read_adc1().unwrap().code, recorded.code);
+//! replay.finish().unwrap();
+//! # }
+//! ```
+
+use core::{cell::RefCell, convert::Infallible, fmt};
+
+use embedded_hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    capture::{ByteWrite, CaptureError, CaptureReader},
+    commands::Command,
+    crc8::{checksum, crc_8_atm},
+    registers::{Adc2Cfg, Interface, Mode2, RegisterMap, ID, REGISTER_COUNT},
+};
+
+/// Magic at the start of a transaction log
+pub const LOG_MAGIC: [u8; 4] = *b"ADST";
+/// Transaction log format version
+pub const LOG_VERSION: u8 = 1;
+
+/// SPI bus wrapper writing every transaction to a log for [Replay]
+pub struct Recorder<SPI, W> {
+    spi: SPI,
+    w: W,
+    header: bool,
+}
+
+/// Error of a [Recorder]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordError<S, W> {
+    /// Error of the SPI bus
+    Spi(S),
+    /// Error writing the log
+    Log(W),
+}
+
+impl<SPI, W: ByteWrite> Recorder<SPI, W> {
+    pub fn new(spi: SPI, w: W) -> Self {
+        Self {
+            spi,
+            w,
+            header: false,
+        }
+    }
+
+    /// Release the SPI bus and the log output
+    pub fn release(self) -> (SPI, W) {
+        (self.spi, self.w)
+    }
+
+    fn begin<S>(&mut self, mosi: &[u8]) -> Result<(), RecordError<S, W::Error>> {
+        if !self.header {
+            self.w.write_all(&LOG_MAGIC).map_err(RecordError::Log)?;
+            self.w.write_all(&[LOG_VERSION]).map_err(RecordError::Log)?;
+            self.header = true;
+        }
+        let len = mosi.len() as u16;
+        self.w
+            .write_all(&len.to_le_bytes())
+            .map_err(RecordError::Log)?;
+        self.w
+            .write_all(&mosi[..usize::from(len)])
+            .map_err(RecordError::Log)
+    }
+
+    /// Write the received bytes, zeros if the transaction failed or received nothing
+    fn end<S>(&mut self, len: usize, miso: Option<&[u8]>) -> Result<(), RecordError<S, W::Error>> {
+        let len = len.min(usize::from(u16::MAX));
+        match miso {
+            Some(miso) => self.w.write_all(&miso[..len]),
+            None => (0..len).try_for_each(|_| self.w.write_all(&[0])),
+        }
+        .map_err(RecordError::Log)
+    }
+}
+
+impl<SPI, W> Transfer<u8> for Recorder<SPI, W>
+where
+    SPI: Transfer<u8>,
+    W: ByteWrite,
+{
+    type Error = RecordError<SPI::Error, W::Error>;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.begin(words)?;
+        let len = words.len();
+        match self.spi.transfer(words) {
+            Ok(miso) => {
+                self.end(len, Some(miso))?;
+                Ok(miso)
+            }
+            Err(e) => {
+                self.end(len, None)?;
+                Err(RecordError::Spi(e))
+            }
+        }
+    }
+}
+
+impl<SPI, W> Write<u8> for Recorder<SPI, W>
+where
+    SPI: Write<u8>,
+    W: ByteWrite,
+{
+    type Error = RecordError<SPI::Error, W::Error>;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.begin(words)?;
+        let res = self.spi.write(words);
+        self.end(words.len(), None)?;
+        res.map_err(RecordError::Spi)
+    }
+}
+
+/// Deviation of a replayed session from the recording
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Transaction log does not start with [LOG_MAGIC] or has an unsupported version
+    BadLog,
+    /// Binary capture could not be read
+    Capture(CaptureError),
+    /// Transaction log ends within a transaction
+    Truncated,
+    /// Driver issued more transactions or data reads than recorded
+    Exhausted { index: usize },
+    /// Transaction has a different length than recorded
+    Length {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// Transaction sent a different byte than recorded
+    Mismatch {
+        index: usize,
+        offset: usize,
+        expected: u8,
+        actual: u8,
+    },
+    /// Data read was not for the ADC and gain of the recorded sample
+    Config { index: usize },
+    /// Session ended with recorded transactions or samples left
+    Unconsumed { remaining: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::BadLog => write!(f, "not an ADS126x transaction log"),
+            ReplayError::Capture(e) => write!(f, "{e}"),
+            ReplayError::Truncated => write!(f, "transaction log is truncated"),
+            ReplayError::Exhausted { index } => {
+                write!(f, "transaction {index} was not recorded")
+            }
+            ReplayError::Length {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "transaction {index}: length {actual}, recorded {expected}"
+            ),
+            ReplayError::Mismatch {
+                index,
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "transaction {index}: byte {offset} is {actual:02X}h, recorded {expected:02X}h"
+            ),
+            ReplayError::Config { index } => {
+                write!(f, "sample {index}: read with a different ADC or gain")
+            }
+            ReplayError::Unconsumed { remaining } => {
+                write!(f, "{remaining} recorded transactions or samples left")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ReplayError {}
+
+impl From<CaptureError> for ReplayError {
+    fn from(e: CaptureError) -> Self {
+        ReplayError::Capture(e)
+    }
+}
+
+enum Source<'a> {
+    /// Remaining entries of a transaction log
+    Log(&'a [u8]),
+    /// Remaining samples of a capture and the registers as written by the driver
+    Capture(CaptureReader<'a>, RegisterMap),
+}
+
+struct State<'a> {
+    source: Source<'a>,
+    /// Index of the next transaction or sample
+    index: usize,
+    error: Option<ReplayError>,
+}
+
+impl State<'_> {
+    fn transfer(&mut self, buf: &mut [u8]) -> Result<(), ReplayError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let res = match &mut self.source {
+            Source::Log(log) => Self::transfer_log(log, self.index, buf),
+            Source::Capture(reader, regs) => Self::transfer_capture(reader, regs, self.index, buf),
+        };
+        match res {
+            Ok(true) => self.index += 1,
+            Ok(false) => {}
+            Err(e) => self.error = Some(e),
+        }
+        res.map(|_| ())
+    }
+
+    /// Replay the next logged transaction, always consumes it
+    fn transfer_log(log: &mut &[u8], index: usize, buf: &mut [u8]) -> Result<bool, ReplayError> {
+        if log.is_empty() {
+            return Err(ReplayError::Exhausted { index });
+        }
+        let len = match log {
+            [lo, hi, ..] => usize::from(u16::from_le_bytes([*lo, *hi])),
+            _ => return Err(ReplayError::Truncated),
+        };
+        if log.len() < 2 + 2 * len {
+            return Err(ReplayError::Truncated);
+        }
+        let (mosi, miso) = log[2..2 + 2 * len].split_at(len);
+        *log = &log[2 + 2 * len..];
+
+        if buf.len() != len {
+            return Err(ReplayError::Length {
+                index,
+                expected: len,
+                actual: buf.len(),
+            });
+        }
+        if let Some((offset, (&expected, &actual))) = mosi
+            .iter()
+            .zip(buf.iter())
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+        {
+            return Err(ReplayError::Mismatch {
+                index,
+                offset,
+                expected,
+                actual,
+            });
+        }
+        buf.copy_from_slice(miso);
+        Ok(true)
+    }
+
+    /// Serve a transaction from a capture, returns true if a sample was consumed
+    fn transfer_capture(
+        reader: &mut CaptureReader<'_>,
+        regs: &mut RegisterMap,
+        index: usize,
+        buf: &mut [u8],
+    ) -> Result<bool, ReplayError> {
+        let Some(&cmd) = buf.first() else {
+            return Ok(false);
+        };
+        let (adc2, frame) = match cmd {
+            0x00 if buf.len() > 1 => (false, &mut buf[..]),
+            c if c == Command::RData1 as u8 => (false, &mut buf[1..]),
+            c if c == Command::RData2 as u8 => (true, &mut buf[1..]),
+            0x20..=0x3F => {
+                let start = usize::from(cmd & 0x1F);
+                for (i, b) in buf.iter_mut().skip(2).enumerate() {
+                    *b = regs.0.get(start + i).copied().unwrap_or(0);
+                }
+                return Ok(false);
+            }
+            0x40..=0x5F => {
+                let start = usize::from(cmd & 0x1F);
+                let count = buf.get(1).map_or(0, |n| usize::from(*n) + 1);
+                for (i, b) in buf.iter().skip(2).take(count).enumerate() {
+                    let addr = start + i;
+                    if addr != ID as usize && addr < REGISTER_COUNT {
+                        regs.0[addr] = *b;
+                    }
+                }
+                return Ok(false);
+            }
+            _ => return Ok(false),
+        };
+
+        let sample = reader.next().ok_or(ReplayError::Exhausted { index })??;
+        let gain = if adc2 {
+            regs.get::<Adc2Cfg>().pga_gain()
+        } else {
+            regs.get::<Mode2>().pga_gain()
+        };
+        if gain != sample.data.gain {
+            return Err(ReplayError::Config { index });
+        }
+
+        let interface = regs.get::<Interface>();
+        let mut out = [0; 6];
+        let mut len = 0;
+        if interface.status() {
+            out[0] = sample.data.status.0;
+            len += 1;
+        }
+        let value = sample.data.code.to_be_bytes();
+        out[len..len + 4].copy_from_slice(&value);
+        len += 4;
+        match interface.crc() {
+            1 => out[len] = checksum(&value),
+            2 => out[len] = crc_8_atm(&value),
+            _ => {}
+        }
+        let n = frame.len().min(out.len());
+        frame[..n].copy_from_slice(&out[..n]);
+        Ok(true)
+    }
+
+    fn remaining(&self) -> usize {
+        match &self.source {
+            Source::Log(log) => {
+                let mut log = *log;
+                let mut n = 0;
+                while let [lo, hi, ..] = log {
+                    let len = 2 + 2 * usize::from(u16::from_le_bytes([*lo, *hi]));
+                    log = log.get(len..).unwrap_or(&[]);
+                    n += 1;
+                }
+                n
+            }
+            Source::Capture(reader, _) => reader.remaining(),
+        }
+    }
+}
+
+/// A virtual ADS126x replaying a transaction log or capture
+///
+/// The device is shared by the SPI bus and pins returned by [Self::spi], [Self::cs], [Self::drdy]
+/// and [Self::rst].
+pub struct Replay<'a> {
+    state: RefCell<State<'a>>,
+}
+
+impl<'a> Replay<'a> {
+    fn new(source: Source<'a>) -> Self {
+        Self {
+            state: RefCell::new(State {
+                source,
+                index: 0,
+                error: None,
+            }),
+        }
+    }
+
+    /// Replay a transaction log written by [Recorder]
+    pub fn from_log(log: &'a [u8]) -> Result<Self, ReplayError> {
+        match log {
+            [m0, m1, m2, m3, LOG_VERSION, entries @ ..] if [*m0, *m1, *m2, *m3] == LOG_MAGIC => {
+                Ok(Self::new(Source::Log(entries)))
+            }
+            _ => Err(ReplayError::BadLog),
+        }
+    }
+
+    /// Replay the samples of a binary [capture](crate::capture)
+    pub fn from_capture(capture: &'a [u8]) -> Result<Self, ReplayError> {
+        let reader = CaptureReader::new(capture)?;
+        let regs = reader.header().registers;
+        Ok(Self::new(Source::Capture(reader, regs)))
+    }
+
+    /// SPI bus connected to the device
+    pub fn spi(&self) -> ReplaySpi<'_, 'a> {
+        ReplaySpi(self)
+    }
+
+    /// CS pin
+    pub fn cs(&self) -> ReplayPin {
+        ReplayPin
+    }
+
+    /// RST pin, resets are not replayed
+    pub fn rst(&self) -> ReplayPin {
+        ReplayPin
+    }
+
+    /// DRDY pin, always low
+    pub fn drdy(&self) -> ReplayDrdy {
+        ReplayDrdy
+    }
+
+    /// Number of recorded transactions (log) or samples (capture) not yet replayed
+    pub fn remaining(&self) -> usize {
+        self.state.borrow().remaining()
+    }
+
+    /// Check that the session matched the recording and consumed all of it
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        let state = self.state.borrow();
+        if let Some(e) = state.error {
+            return Err(e);
+        }
+        match state.remaining() {
+            0 => Ok(()),
+            remaining => Err(ReplayError::Unconsumed { remaining }),
+        }
+    }
+}
+
+/// SPI bus of a [Replay]
+pub struct ReplaySpi<'r, 'a>(&'r Replay<'a>);
+
+impl Transfer<u8> for ReplaySpi<'_, '_> {
+    type Error = ReplayError;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.0.state.borrow_mut().transfer(words)?;
+        Ok(words)
+    }
+}
+
+impl Write<u8> for ReplaySpi<'_, '_> {
+    type Error = ReplayError;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let mut buf = [0; 2 + REGISTER_COUNT];
+        let buf = buf.get_mut(..words.len()).ok_or(ReplayError::Length {
+            index: self.0.state.borrow().index,
+            expected: 2 + REGISTER_COUNT,
+            actual: words.len(),
+        })?;
+        buf.copy_from_slice(words);
+        self.0.state.borrow_mut().transfer(buf)
+    }
+}
+
+/// Output pin of a [Replay], ignored
+pub struct ReplayPin;
+
+impl OutputPin for ReplayPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// DRDY pin of a [Replay], always low
+pub struct ReplayDrdy;
+
+impl InputPin for ReplayDrdy {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::convert::Infallible;
+    use std::vec::Vec;
+
+    use super::{Recorder, Replay, ReplayError};
+    use crate::{
+        capture::{BinarySink, CaptureHeader, Sample, SampleSink},
+        device::Ads126xError,
+        registers::{IdDev, InpMux, InpMuxMuxx, Interface, Mode2, Mode2Dr, Mode2Gain},
+        sim::SimAds126x,
+        Ads1263,
+    };
+
+    #[test]
+    fn test_replay_log() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 250_000);
+        let mut log = Vec::new();
+        let recorded = {
+            let spi = Recorder::new(sim.spi(), |b: &[u8]| {
+                log.extend_from_slice(b);
+                Ok::<(), Infallible>(())
+            });
+            let mut adc = Ads1263::probe(spi, sim.cs(), sim.drdy(), sim.rst()).unwrap();
+            adc.write_reg(Interface(0b110)).unwrap();
+            adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+                .unwrap();
+            adc.start1().unwrap();
+            adc.read_adc1().unwrap()
+        };
+
+        let replay = Replay::from_log(&log).unwrap();
+        let mut adc =
+            Ads1263::probe(replay.spi(), replay.cs(), replay.drdy(), replay.rst()).unwrap();
+        adc.write_reg(Interface(0b110)).unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        assert_eq!(
+            replay.finish(),
+            Err(ReplayError::Unconsumed { remaining: 2 })
+        );
+        adc.start1().unwrap();
+        assert_eq!(adc.read_adc1().unwrap().code, recorded.code);
+        replay.finish().unwrap();
+
+        // Changed register sequence is detected
+        let replay = Replay::from_log(&log).unwrap();
+        let mut adc =
+            Ads1263::probe(replay.spi(), replay.cs(), replay.drdy(), replay.rst()).unwrap();
+        adc.write_reg(Interface(0b101)).unwrap_err();
+        let err = ReplayError::Mismatch {
+            index: 1,
+            offset: 2,
+            expected: 0b110,
+            actual: 0b101,
+        };
+        assert_eq!(replay.finish(), Err(err));
+        assert!(matches!(adc.start1(), Err(Ads126xError::SpiErr(e)) if e == err));
+    }
+
+    #[test]
+    fn test_replay_capture() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 250_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain2, false))
+            .unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.start1().unwrap();
+        adc.sync_registers().unwrap();
+
+        let mut bytes = Vec::new();
+        let mut sink = BinarySink::new(
+            |b: &[u8]| {
+                bytes.extend_from_slice(b);
+                Ok::<(), Infallible>(())
+            },
+            CaptureHeader::new(IdDev::Ads1263, *adc.registers()),
+        );
+        for t in 0..3 {
+            sink.write(&Sample::new(0, t, adc.read_adc1().unwrap()))
+                .unwrap();
+        }
+        let _ = sink.finish().unwrap();
+
+        let replay = Replay::from_capture(&bytes).unwrap();
+        let mut adc =
+            Ads1263::probe(replay.spi(), replay.cs(), replay.drdy(), replay.rst()).unwrap();
+        adc.sync_registers().unwrap();
+        for _ in 0..2 {
+            assert_eq!(adc.read_adc1().unwrap().to_microvolts(), 250_000);
+        }
+        // Gain differs from the recording
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain4, false))
+            .unwrap();
+        adc.read_adc1().unwrap_err();
+        assert_eq!(replay.finish(), Err(ReplayError::Config { index: 2 }));
+    }
+}
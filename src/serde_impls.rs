@@ -0,0 +1,204 @@
+//! Serialization using [serde]
+//!
+//! Registers serialize as their raw byte together with the decoded fields, e.g. `Mode2` as
+//! `{ raw = 0x04, dr = 4, gain = 0, bypass = false }`. When deserializing, the register is restored
+//! from `raw` and the decoded fields must match it.
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    device::Status,
+    registers::{for_each_register, *},
+};
+
+macro_rules! impl_serde {
+    ($typ:ident { $($field:ident: $fty:ty),* $(,)? }) => {
+        const _: () = {
+            #[derive(Serialize, Deserialize)]
+            struct Repr {
+                raw: u8,
+                $($field: $fty),*
+            }
+
+            impl Serialize for $typ {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    Repr {
+                        raw: self.0,
+                        $($field: self.$field()),*
+                    }
+                    .serialize(serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $typ {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let repr = Repr::deserialize(deserializer)?;
+                    let reg = $typ(repr.raw);
+                    $(
+                        if reg.$field() != repr.$field {
+                            return Err(D::Error::custom(concat!(
+                                stringify!($typ), ".", stringify!($field), " does not match raw value"
+                            )));
+                        }
+                    )*
+                    Ok(reg)
+                }
+            }
+        };
+    };
+}
+
+for_each_register!(impl_serde);
+
+/// Named registers of a [RegisterMap]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "RegisterMap")]
+struct RegisterMapRepr {
+    id: Id,
+    power: Power,
+    interface: Interface,
+    mode0: Mode0,
+    mode1: Mode1,
+    mode2: Mode2,
+    inpmux: InpMux,
+    idacmux: IdacMux,
+    idacmag: IdacMag,
+    refmux: RefMux,
+    tdacp: TdacP,
+    tdacn: TdacN,
+    gpiocon: GpioCon,
+    gpiodir: GpioDir,
+    gpiodat: GpioDat,
+    adc2cfg: Adc2Cfg,
+    adc2mux: Adc2Mux,
+    calibration: Calibration,
+}
+
+impl Serialize for RegisterMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RegisterMapRepr {
+            id: self.get(),
+            power: self.get(),
+            interface: self.get(),
+            mode0: self.get(),
+            mode1: self.get(),
+            mode2: self.get(),
+            inpmux: self.get(),
+            idacmux: self.get(),
+            idacmag: self.get(),
+            refmux: self.get(),
+            tdacp: self.get(),
+            tdacn: self.get(),
+            gpiocon: self.get(),
+            gpiodir: self.get(),
+            gpiodat: self.get(),
+            adc2cfg: self.get(),
+            adc2mux: self.get(),
+            calibration: self.calibration(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RegisterMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = RegisterMapRepr::deserialize(deserializer)?;
+        let mut regs = RegisterMap::default();
+        regs.set(repr.id);
+        regs.set(repr.power);
+        regs.set(repr.interface);
+        regs.set(repr.mode0);
+        regs.set(repr.mode1);
+        regs.set(repr.mode2);
+        regs.set(repr.inpmux);
+        regs.set(repr.idacmux);
+        regs.set(repr.idacmag);
+        regs.set(repr.refmux);
+        regs.set(repr.tdacp);
+        regs.set(repr.tdacn);
+        regs.set(repr.gpiocon);
+        regs.set(repr.gpiodir);
+        regs.set(repr.gpiodat);
+        regs.set(repr.adc2cfg);
+        regs.set(repr.adc2mux);
+        regs.set_calibration(&repr.calibration);
+        Ok(regs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use crate::{
+        device::{Data, Status},
+        registers::{Calibration, Mode2, Mode2Dr, Mode2Gain, Power, RegisterMap},
+    };
+
+    fn snapshot() -> RegisterMap {
+        let mut regs = RegisterMap::default();
+        regs.set(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false));
+        regs.set(Power::with(true, true, false));
+        regs.set_calibration(&Calibration {
+            ofcal1: -1234,
+            fscal1: 0x400123,
+            ofcal2: -12,
+            fscal2: 0x4001,
+        });
+        regs
+    }
+
+    #[test]
+    fn test_register_fields() {
+        let json = serde_json::to_value(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false));
+        assert_eq!(
+            json.unwrap(),
+            serde_json::json!({"raw": 0x38, "dr": 8, "gain": 3, "bypass": false})
+        );
+
+        let bad = r#"{"raw": 56, "dr": 8, "gain": 4, "bypass": false}"#;
+        assert!(serde_json::from_str::<Mode2>(bad).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let regs = snapshot();
+        let cal = regs.calibration();
+
+        let json = serde_json::to_string(&regs).unwrap();
+        assert_eq!(serde_json::from_str::<RegisterMap>(&json).unwrap(), regs);
+        let json = serde_json::to_string(&cal).unwrap();
+        assert_eq!(serde_json::from_str::<Calibration>(&json).unwrap(), cal);
+
+        let toml = toml::to_string(&regs).unwrap();
+        assert_eq!(toml::from_str::<RegisterMap>(&toml).unwrap(), regs);
+        let toml = toml::to_string(&cal).unwrap();
+        assert_eq!(toml::from_str::<Calibration>(&toml).unwrap(), cal);
+
+        let bytes = postcard::to_allocvec(&regs).unwrap();
+        assert_eq!(postcard::from_bytes::<RegisterMap>(&bytes).unwrap(), regs);
+        let bytes = postcard::to_allocvec(&cal).unwrap();
+        assert_eq!(postcard::from_bytes::<Calibration>(&bytes).unwrap(), cal);
+    }
+
+    #[test]
+    fn test_data_roundtrip() {
+        let data = Data {
+            status: Status(0x41),
+            code: -123456,
+            vref_uv: 2_500_000,
+            gain: 8,
+        };
+
+        let json = serde_json::to_string(&data).unwrap();
+        let back: Data = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.status.0, back.code, back.gain), (0x41, -123456, 8));
+
+        let bytes = postcard::to_allocvec(&data).unwrap();
+        let back: Data = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            (back.status.0, back.code, back.vref_uv),
+            (0x41, -123456, 2_500_000)
+        );
+    }
+}
@@ -0,0 +1,794 @@
+//! Simulated ADS126x for testing without hardware
+//!
+//! [SimAds126x] answers commands, register accesses and data reads like a real device. Conversion
+//! results are computed from the configured input voltages, reference, PGA gain and calibration
+//! registers. Conversions complete instantly: while an ADC is running DRDY and the status byte
+//! report new data on every poll, in pulse mode one conversion is available per start.
+//!
+//! The analog model is deliberately simple. PGA alarms are raised when the result clips, the
+//! reference alarm when the reference is below 0.4 V, the filter and its settling are not modelled.
+//!
+//! Hardware faults can be injected with [SimAds126x::set_faults] and [SimAds126x::inject_reset] to
+//! exercise error handling and recovery.
+//!
+//! ```
+//! use ads126x::{
+//!     registers::{InpMux, InpMuxMuxx},
+//!     sim::SimAds126x,
+//!     Ads1263,
+//! };
+//!
+//! let sim = SimAds126x::ads1263();
+//! sim.set_input(InpMuxMuxx::Ain0, 1_000_000);
+//!
+//! let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+//! adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0)).unwrap();
+//! adc.start1().unwrap();
+//! let data = adc.read_adc1().unwrap();
+//! assert_eq!(data.to_microvolts(), 1_000_000);
+//! ```
+
+use core::{cell::RefCell, convert::Infallible};
+
+use embedded_hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    crc8::{checksum, crc_8_atm},
+    device::INTERNAL_REF_UV,
+    registers::*,
+};
+
+/// Analog input index of AINCOM
+const AINCOM: usize = 10;
+/// TDAC output as fraction of the analog supply in units of 1e-7, indexed by MAGP/MAGN
+const TDAC_RATIO: [(u8, i64); 19] = [
+    (0b01001, 9_000_000),
+    (0b01000, 7_000_000),
+    (0b00111, 6_000_000),
+    (0b00110, 5_500_000),
+    (0b00101, 5_250_000),
+    (0b00100, 5_125_000),
+    (0b00011, 5_062_500),
+    (0b00010, 5_031_250),
+    (0b00001, 5_015_625),
+    (0b00000, 5_000_000),
+    (0b10001, 4_984_375),
+    (0b10010, 4_968_750),
+    (0b10011, 4_937_500),
+    (0b10100, 4_875_000),
+    (0b10101, 4_750_000),
+    (0b10110, 4_500_000),
+    (0b10111, 4_000_000),
+    (0b11000, 3_000_000),
+    (0b11001, 1_000_000),
+];
+/// Reference alarm threshold
+const REF_ALARM_UV: i64 = 400_000;
+
+/// Faults injected by a [SimAds126x]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Faults {
+    /// Invert the checksum/CRC byte of data frames
+    pub corrupt_crc: bool,
+    /// Hold DRDY at a level, `Some(true)` is stuck high (never ready)
+    pub drdy_stuck: Option<bool>,
+    /// Bits set in every status byte, e.g. `1 << 4` for the reference alarm
+    pub status: u8,
+    /// Replace every byte received by the host, `Some(0xFF)` or `Some(0x00)` for a floating or shorted DOUT
+    pub miso_stuck: Option<u8>,
+    /// Number of bytes at the start of every transaction the device does not see
+    pub dropped_bytes: usize,
+}
+
+/// State of the simulated device
+struct State {
+    dev: IdDev,
+    regs: RegisterMap,
+    /// Voltages of AIN0..AIN9 and AINCOM in uV
+    ain: [i64; 11],
+    avdd_uv: i64,
+    dvdd_uv: i64,
+    temp_mc: i64,
+    /// Offset of the ADC inputs in uV, removed by self offset calibration
+    offset_uv: i64,
+    /// Amplitude of pseudo-random noise added to conversions in codes
+    noise: u32,
+    rng: u32,
+    gpio_in: u8,
+    adc1_running: bool,
+    adc2_running: bool,
+    adc1_new: bool,
+    adc2_new: bool,
+    rst_low: bool,
+    start_high: bool,
+    faults: Faults,
+}
+
+impl State {
+    fn new(dev: IdDev) -> Self {
+        let mut this = Self {
+            dev,
+            regs: RegisterMap::default(),
+            ain: [0; 11],
+            avdd_uv: 5_000_000,
+            dvdd_uv: 3_300_000,
+            temp_mc: 25_000,
+            offset_uv: 0,
+            noise: 0,
+            rng: 0x1234_5678,
+            gpio_in: 0,
+            adc1_running: false,
+            adc2_running: false,
+            adc1_new: false,
+            adc2_new: false,
+            rst_low: false,
+            start_high: false,
+            faults: Faults::default(),
+        };
+        this.reset();
+        this
+    }
+
+    fn reset(&mut self) {
+        self.regs = RegisterMap::default();
+        self.regs.0[ID as usize] = ((self.dev as u8) << 5) | 0x01;
+        self.adc1_running = self.start_high;
+        self.adc2_running = false;
+        self.adc1_new = false;
+        self.adc2_new = false;
+    }
+
+    /// Voltage of a single input in uV
+    fn pin_uv(&self, mux: u8) -> i64 {
+        self.ain.get(mux as usize).copied().unwrap_or(0)
+    }
+
+    fn tdac_uv(&self, mag: u8) -> i64 {
+        let ratio = TDAC_RATIO
+            .iter()
+            .find(|(m, _)| *m == mag)
+            .map_or(5_000_000, |(_, r)| *r);
+        self.avdd_uv * ratio / 10_000_000
+    }
+
+    /// Differential input voltage in uV selected by a MUXP/MUXN pair
+    fn input_uv(&self, muxp: u8, muxn: u8) -> i64 {
+        match (muxp, muxn) {
+            (11, 11) => 122_400 + (self.temp_mc - 25_000) * 420 / 1000,
+            (12, 12) => self.avdd_uv / 4,
+            (13, 13) => self.dvdd_uv / 4,
+            (14, 14) => {
+                self.tdac_uv(self.regs.get::<TdacP>().magp())
+                    - self.tdac_uv(self.regs.get::<TdacN>().magn())
+            }
+            (p, n) => self.pin_uv(p) - self.pin_uv(n),
+        }
+    }
+
+    fn vref1_uv(&self) -> i64 {
+        let refmux = self.regs.get::<RefMux>();
+        let p = match refmux.rmuxp() {
+            0 if self.regs.get::<Power>().intref() => i64::from(INTERNAL_REF_UV),
+            1 => self.ain[0],
+            2 => self.ain[2],
+            3 => self.ain[4],
+            4 => self.avdd_uv,
+            _ => 0,
+        };
+        let n = match refmux.rmuxn() {
+            1 => self.ain[1],
+            2 => self.ain[3],
+            3 => self.ain[5],
+            _ => 0,
+        };
+        if self.regs.get::<Mode0>().refrev() {
+            n - p
+        } else {
+            p - n
+        }
+    }
+
+    fn vref2_uv(&self) -> i64 {
+        match self.regs.get::<Adc2Cfg>().ref2() {
+            0 if self.regs.get::<Power>().intref() => i64::from(INTERNAL_REF_UV),
+            1 => self.ain[0] - self.ain[1],
+            2 => self.ain[2] - self.ain[3],
+            3 => self.ain[4] - self.ain[5],
+            4 => self.avdd_uv,
+            _ => 0,
+        }
+    }
+
+    fn noise(&mut self) -> i64 {
+        if self.noise == 0 {
+            return 0;
+        }
+        // xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        i64::from(self.rng % (2 * self.noise + 1)) - i64::from(self.noise)
+    }
+
+    /// Uncalibrated ADC1 result, full scale 2^31, and status alarm bits
+    fn convert1_raw(&mut self) -> (i64, u8) {
+        let inpmux = self.regs.get::<InpMux>();
+        let vin = self.input_uv(inpmux.muxp(), inpmux.muxn()) + self.offset_uv;
+        let vref = self.vref1_uv();
+        let gain = i64::from(self.regs.get::<Mode2>().pga_gain());
+        let mut alarms = 0;
+        if vref.abs() < REF_ALARM_UV {
+            alarms |= 1 << 4;
+        }
+        let raw = if vref == 0 {
+            0
+        } else {
+            (((i128::from(vin) * i128::from(gain)) << 31) / i128::from(vref)) as i64 + self.noise()
+        };
+        (raw, alarms)
+    }
+
+    /// Calibrated ADC1 conversion result
+    fn convert1(&mut self) -> (i32, u8) {
+        let (raw, mut alarms) = self.convert1_raw();
+        let cal = self.regs.calibration();
+        let code = (raw - (i64::from(cal.ofcal1) << 8)) * i64::from(cal.fscal1) / 0x40_0000;
+        if code > i64::from(i32::MAX) {
+            alarms |= 1 << 2;
+        } else if code < i64::from(i32::MIN) {
+            alarms |= 1 << 3;
+        }
+        (code.clamp(i32::MIN.into(), i32::MAX.into()) as i32, alarms)
+    }
+
+    /// Uncalibrated ADC2 result, full scale 2^23, and status alarm bits
+    fn convert2_raw(&mut self) -> (i64, u8) {
+        let adc2mux = self.regs.get::<Adc2Mux>();
+        let vin = self.input_uv(adc2mux.muxp2(), adc2mux.muxn2()) + self.offset_uv;
+        let vref = self.vref2_uv();
+        let gain = i64::from(self.regs.get::<Adc2Cfg>().pga_gain());
+        let raw = if vref == 0 {
+            0
+        } else {
+            (((i128::from(vin) * i128::from(gain)) << 23) / i128::from(vref)) as i64
+                + (self.noise() >> 8)
+        };
+        (raw, 0)
+    }
+
+    /// Calibrated ADC2 conversion result, left aligned to 32 bits
+    fn convert2(&mut self) -> (i32, u8) {
+        let (raw, alarms) = self.convert2_raw();
+        let cal = self.regs.calibration();
+        let code = (raw - i64::from(cal.ofcal2)) * i64::from(cal.fscal2) / 0x4000;
+        let code = code.clamp(-(1 << 23), (1 << 23) - 1) as i32;
+        (code << 8, alarms)
+    }
+
+    fn status(&self, alarms: u8) -> u8 {
+        let mut status = alarms | self.faults.status;
+        if self.regs.get::<Power>().reset() {
+            status |= 1;
+        }
+        if self.adc1_new {
+            status |= 1 << 6;
+        }
+        if self.adc2_new {
+            status |= 1 << 7;
+        }
+        status
+    }
+
+    /// Update ADC1 after a conversion has been read
+    fn adc1_read(&mut self) {
+        self.adc1_new = false;
+        if self.regs.get::<Mode0>().run_mode() {
+            self.adc1_running = false;
+        }
+    }
+
+    /// Fill `out` with a data frame (status, data, checksum/CRC as enabled in INTERFACE)
+    fn frame(&mut self, adc2: bool, out: &mut [u8]) {
+        // ADC2 converts alongside ADC1, so its flag is also set in ADC1 frames
+        self.adc2_new |= self.adc2_running;
+        if !adc2 {
+            self.adc1_new |= self.adc1_running;
+        }
+        let (code, alarms) = if adc2 {
+            self.convert2()
+        } else {
+            self.convert1()
+        };
+        let interface = self.regs.get::<Interface>();
+
+        let mut frame = [0; 6];
+        let mut len = 0;
+        if interface.status() {
+            frame[0] = self.status(alarms);
+            len += 1;
+        }
+        let value = code.to_be_bytes();
+        frame[len..len + 4].copy_from_slice(&value);
+        len += 4;
+        match interface.crc() {
+            1 => frame[len] = checksum(&value),
+            2 => frame[len] = crc_8_atm(&value),
+            _ => {}
+        }
+        if self.faults.corrupt_crc && interface.crc() > 0 {
+            frame[len] = !frame[len];
+        }
+
+        for (o, b) in out.iter_mut().zip(frame.iter()) {
+            *o = *b;
+        }
+        if adc2 {
+            self.adc2_new = false;
+        } else {
+            self.adc1_read();
+        }
+    }
+
+    fn calibrate(&mut self, cmd: u8) {
+        match cmd {
+            // SFOCAL1: offset with inputs shorted internally
+            0x19 => {
+                let inpmux = self.regs.get::<InpMux>();
+                self.regs
+                    .set(InpMux::differential(InpMuxMuxx::Float, InpMuxMuxx::Float));
+                let (raw, _) = self.convert1_raw();
+                self.regs.set(inpmux);
+                self.set_ofcal1(raw);
+            }
+            // SYOCAL1: offset of the present input
+            0x16 => {
+                let (raw, _) = self.convert1_raw();
+                self.set_ofcal1(raw);
+            }
+            // SYGCAL1: present input is full scale
+            0x17 => {
+                let (raw, _) = self.convert1_raw();
+                let raw = raw - (i64::from(self.regs.calibration().ofcal1) << 8);
+                if raw > 0 {
+                    let mut cal = self.regs.calibration();
+                    cal.fscal1 = ((0x40_0000 * i64::from(i32::MAX)) / raw).min(0xFF_FFFF) as u32;
+                    self.regs.set_calibration(&cal);
+                }
+            }
+            // SFOCAL2, SYOCAL2
+            0x1B | 0x1E => {
+                let adc2mux = self.regs.get::<Adc2Mux>();
+                if cmd == 0x1E {
+                    self.regs.set(Adc2Mux(0xFF));
+                }
+                let (raw, _) = self.convert2_raw();
+                self.regs.set(adc2mux);
+                let mut cal = self.regs.calibration();
+                cal.ofcal2 = raw.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+                self.regs.set_calibration(&cal);
+            }
+            _ => {}
+        }
+        if cmd < 0x1B {
+            self.adc1_new = true;
+        } else {
+            self.adc2_new = true;
+        }
+    }
+
+    fn set_ofcal1(&mut self, raw: i64) {
+        let mut cal = self.regs.calibration();
+        cal.ofcal1 = (raw >> 8).clamp(-(1 << 23), (1 << 23) - 1) as i32;
+        self.regs.set_calibration(&cal);
+    }
+
+    fn read_reg(&self, addr: usize) -> u8 {
+        match addr {
+            a if a == GPIODAT as usize => {
+                let inputs = self.regs.0[GPIODIR as usize] & self.regs.0[GPIOCON as usize];
+                (self.regs.0[a] & !inputs) | (self.gpio_in & inputs)
+            }
+            a if a < REGISTER_COUNT => self.regs.0[a],
+            _ => 0,
+        }
+    }
+
+    fn write_reg(&mut self, addr: usize, b: u8) {
+        match addr {
+            a if a == ID as usize => {}
+            // RESET can only be cleared
+            a if a == POWER as usize => self.regs.0[a] = b & (self.regs.0[a] | !0x10),
+            a if a < REGISTER_COUNT => self.regs.0[a] = b,
+            _ => {}
+        }
+    }
+
+    /// Handle one SPI transaction with faults applied, replacing `buf` with the response
+    fn transfer(&mut self, buf: &mut [u8]) {
+        let skip = self.faults.dropped_bytes.min(buf.len());
+        self.execute(&mut buf[skip..]);
+        if let Some(b) = self.faults.miso_stuck {
+            buf.fill(b);
+        }
+    }
+
+    /// Execute one SPI transaction, replacing `buf` with the response
+    fn execute(&mut self, buf: &mut [u8]) {
+        let Some(&cmd) = buf.first() else {
+            return;
+        };
+        match cmd {
+            // NOP, data is clocked out directly when DIN is held low
+            0x00 if buf.len() > 1 => self.frame(false, buf),
+            0x06 | 0x07 => self.reset(),
+            0x08 | 0x09 => {
+                self.adc1_running = true;
+                self.adc1_new = true;
+            }
+            0x0A | 0x0B => self.adc1_running = false,
+            0x0C | 0x0D if self.dev == IdDev::Ads1263 => {
+                self.adc2_running = true;
+                self.adc2_new = true;
+            }
+            0x0E | 0x0F => self.adc2_running = false,
+            0x12 | 0x13 => self.frame(false, &mut buf[1..]),
+            0x14 | 0x15 => self.frame(true, &mut buf[1..]),
+            0x16 | 0x17 | 0x19 => self.calibrate(cmd),
+            0x1B | 0x1C | 0x1E if self.dev == IdDev::Ads1263 => self.calibrate(cmd),
+            0x20..=0x3F => {
+                let start = usize::from(cmd & 0x1F);
+                for (i, b) in buf.iter_mut().skip(2).enumerate() {
+                    *b = self.read_reg(start + i);
+                }
+            }
+            0x40..=0x5F => {
+                let start = usize::from(cmd & 0x1F);
+                let count = buf.get(1).map_or(0, |n| usize::from(*n) + 1);
+                for (i, b) in buf.iter().skip(2).take(count).enumerate() {
+                    self.write_reg(start + i, *b);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A simulated ADS1262 or ADS1263
+///
+/// The device is shared by the SPI bus and pins returned by [Self::spi], [Self::cs], [Self::drdy],
+/// [Self::rst] and [Self::start].
+pub struct SimAds126x {
+    state: RefCell<State>,
+}
+
+impl SimAds126x {
+    /// Simulated device after power-on
+    pub fn new(dev: IdDev) -> Self {
+        Self {
+            state: RefCell::new(State::new(dev)),
+        }
+    }
+
+    /// Simulated ADS1262
+    pub fn ads1262() -> Self {
+        Self::new(IdDev::Ads1262)
+    }
+
+    /// Simulated ADS1263
+    pub fn ads1263() -> Self {
+        Self::new(IdDev::Ads1263)
+    }
+
+    /// SPI bus connected to the device
+    pub fn spi(&self) -> SimSpi<'_> {
+        SimSpi(self)
+    }
+
+    /// CS pin
+    pub fn cs(&self) -> SimPin<'_> {
+        SimPin(self, PinKind::Cs)
+    }
+
+    /// DRDY pin, low while ADC1 has new data
+    pub fn drdy(&self) -> SimDrdy<'_> {
+        SimDrdy(self)
+    }
+
+    /// RST pin, the device resets on the rising edge
+    pub fn rst(&self) -> SimPin<'_> {
+        SimPin(self, PinKind::Rst)
+    }
+
+    /// START pin, ADC1 converts while high (continuous mode) or once per rising edge (pulse mode)
+    pub fn start(&self) -> SimPin<'_> {
+        SimPin(self, PinKind::Start)
+    }
+
+    /// Set the voltage on an analog input (AIN0..AIN9 or AINCOM) in uV relative to AVSS
+    pub fn set_input(&self, input: InpMuxMuxx, uv: i32) {
+        let idx = input as usize;
+        if idx <= AINCOM {
+            self.state.borrow_mut().ain[idx] = i64::from(uv);
+        }
+    }
+
+    /// Set analog (AVDD - AVSS) and digital (DVDD - DGND) supply voltages in uV
+    pub fn set_supplies(&self, avdd_uv: u32, dvdd_uv: u32) {
+        let mut state = self.state.borrow_mut();
+        state.avdd_uv = i64::from(avdd_uv);
+        state.dvdd_uv = i64::from(dvdd_uv);
+    }
+
+    /// Set die temperature in m°C
+    pub fn set_temperature(&self, millicelsius: i32) {
+        self.state.borrow_mut().temp_mc = i64::from(millicelsius);
+    }
+
+    /// Set offset voltage of the ADC inputs in uV, removed by the offset calibration commands
+    pub fn set_offset(&self, uv: i32) {
+        self.state.borrow_mut().offset_uv = i64::from(uv);
+    }
+
+    /// Add pseudo-random noise of up to +-`codes` to ADC1 conversions
+    pub fn set_noise(&self, codes: u32) {
+        self.state.borrow_mut().noise = codes;
+    }
+
+    /// Set the level of GPIOs configured as inputs
+    pub fn set_gpio_inputs(&self, levels: u8) {
+        self.state.borrow_mut().gpio_in = levels;
+    }
+
+    /// Inject faults, replacing the previous ones
+    pub fn set_faults(&self, faults: Faults) {
+        self.state.borrow_mut().faults = faults;
+    }
+
+    /// Currently injected faults
+    pub fn faults(&self) -> Faults {
+        self.state.borrow().faults
+    }
+
+    /// Reset the device as by a supply glitch: registers return to their defaults with
+    /// [Power::reset] set and conversions stop unless START is held high
+    pub fn inject_reset(&self) {
+        self.state.borrow_mut().reset();
+    }
+
+    /// Current register contents
+    pub fn registers(&self) -> RegisterMap {
+        self.state.borrow().regs
+    }
+
+    /// ADC1 is converting
+    pub fn adc1_running(&self) -> bool {
+        self.state.borrow().adc1_running
+    }
+
+    /// ADC2 is converting
+    pub fn adc2_running(&self) -> bool {
+        self.state.borrow().adc2_running
+    }
+}
+
+/// SPI bus of a [SimAds126x]
+pub struct SimSpi<'a>(&'a SimAds126x);
+
+impl Transfer<u8> for SimSpi<'_> {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.0.state.borrow_mut().transfer(words);
+        Ok(words)
+    }
+}
+
+impl Write<u8> for SimSpi<'_> {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let mut buf = [0; 2 + REGISTER_COUNT];
+        let len = words.len().min(buf.len());
+        buf[..len].copy_from_slice(&words[..len]);
+        self.0.state.borrow_mut().transfer(&mut buf[..len]);
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PinKind {
+    Cs,
+    Rst,
+    Start,
+}
+
+/// Output pin of a [SimAds126x]
+pub struct SimPin<'a>(&'a SimAds126x, PinKind);
+
+impl OutputPin for SimPin<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.0.state.borrow_mut();
+        match self.1 {
+            PinKind::Cs => {}
+            PinKind::Rst => state.rst_low = true,
+            PinKind::Start => {
+                state.start_high = false;
+                if !state.regs.get::<Mode0>().run_mode() {
+                    state.adc1_running = false;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.0.state.borrow_mut();
+        match self.1 {
+            PinKind::Cs => {}
+            PinKind::Rst => {
+                if state.rst_low {
+                    state.rst_low = false;
+                    state.reset();
+                }
+            }
+            PinKind::Start => {
+                if !state.start_high {
+                    state.start_high = true;
+                    state.adc1_running = true;
+                    state.adc1_new = true;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DRDY pin of a [SimAds126x]
+pub struct SimDrdy<'a>(&'a SimAds126x);
+
+impl InputPin for SimDrdy<'_> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.is_low().map(|low| !low)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        let mut state = self.0.state.borrow_mut();
+        if let Some(high) = state.faults.drdy_stuck {
+            return Ok(!high);
+        }
+        state.adc1_new |= state.adc1_running;
+        Ok(state.adc1_new)
+    }
+}
+
+/// Delay that returns immediately, the simulated device has no timing
+#[cfg(test)]
+pub(crate) struct NoDelay;
+
+#[cfg(test)]
+impl embedded_hal::blocking::delay::DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+#[cfg(test)]
+impl embedded_hal::blocking::delay::DelayMs<u32> for NoDelay {
+    fn delay_ms(&mut self, _ms: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Faults, NoDelay, SimAds126x};
+    use crate::{
+        device::Ads126xError,
+        registers::{
+            Adc2Cfg, Adc2CfgDr, Adc2CfgGain, Adc2CfgRef, Adc2Mux, InpMux, InpMuxMuxx, Interface,
+            Mode2, Mode2Dr, Mode2Gain, Power,
+        },
+        Ads1263,
+    };
+
+    #[test]
+    fn test_sim() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain2, 100_000);
+        sim.set_input(InpMuxMuxx::Ain3, -50_000);
+        sim.set_offset(120);
+
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.write_reg(Interface(0b110)).unwrap();
+        adc.write_reg(InpMux::differential(InpMuxMuxx::Ain2, InpMuxMuxx::Ain3))
+            .unwrap();
+        adc.write_reg(Mode2::with(Mode2Dr::Sps400, Mode2Gain::Gain8, false))
+            .unwrap();
+        adc.start1().unwrap();
+        assert!(adc.drdy().unwrap());
+
+        let data = adc.read_adc1().unwrap();
+        assert!(data.status.adc1());
+        assert_eq!(data.to_microvolts(), 150_120);
+
+        adc.sfocal1().unwrap();
+        assert!(adc.read_calibration().unwrap().ofcal1 != 0);
+        let data = adc.read_adc1().unwrap();
+        assert!((data.to_microvolts() - 150_000).abs() <= 1);
+
+        adc.set_intref(true).unwrap();
+        adc.write_reg(Adc2Mux(0x23)).unwrap();
+        adc.write_reg(Adc2Cfg::with(
+            Adc2CfgDr::Sps100,
+            Adc2CfgRef::InternalRef,
+            Adc2CfgGain::None,
+        ))
+        .unwrap();
+        adc.start2().unwrap();
+        let data = adc.read_adc2().unwrap();
+        assert!((data.to_microvolts() - 150_120).abs() <= 1);
+
+        adc.reset(NoDelay).unwrap();
+        assert_eq!(adc.read_reg::<Mode2>().unwrap().0, 0x04);
+        assert!(!sim.adc1_running());
+    }
+
+    #[test]
+    fn test_faults() {
+        let sim = SimAds126x::ads1263();
+        sim.set_input(InpMuxMuxx::Ain0, 100_000);
+        let mut adc = Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()).unwrap();
+        adc.clear_reset_flag().unwrap();
+        adc.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            .unwrap();
+        adc.start1().unwrap();
+
+        sim.set_faults(Faults {
+            corrupt_crc: true,
+            ..Default::default()
+        });
+        assert!(matches!(adc.read_adc1(), Err(Ads126xError::Crc(_))));
+
+        sim.set_faults(Faults {
+            drdy_stuck: Some(true),
+            ..Default::default()
+        });
+        assert!(!adc.drdy().unwrap());
+
+        for bit in 1..6 {
+            sim.set_faults(Faults {
+                status: 1 << bit,
+                ..Default::default()
+            });
+            let status = adc.read_adc1().unwrap().status;
+            assert_eq!(status.0 & 0x3F, 1 << bit);
+        }
+
+        for b in [0x00, 0xFF] {
+            sim.set_faults(Faults {
+                miso_stuck: Some(b),
+                ..Default::default()
+            });
+            assert!(matches!(
+                Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst()),
+                Err(Ads126xError::NoDevice)
+            ));
+        }
+
+        sim.set_faults(Faults {
+            dropped_bytes: 2,
+            ..Default::default()
+        });
+        assert!(matches!(adc.read_adc1(), Err(Ads126xError::Crc(_))));
+
+        sim.set_faults(Faults::default());
+        assert_eq!(adc.read_adc1().unwrap().to_microvolts(), 100_000);
+        sim.inject_reset();
+        assert!(adc.read_reg::<Power>().unwrap().reset());
+        assert!(matches!(adc.read_adc1(), Err(Ads126xError::DeviceReset)));
+        assert!(!adc.read_reg::<Power>().unwrap().reset());
+    }
+}
@@ -0,0 +1,207 @@
+//! Synchronized conversions on several devices sharing a START line
+
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::{
+    device::{pulse_start, Ads126x, Ads126xError, Data, Sealed},
+    registers::Register,
+    PwdnNoConnection, StartNoConnection,
+};
+
+/// A group of devices with their START pins tied to a single shared START line.
+///
+/// All devices should be configured for pulse conversion mode ([Mode0::run_mode](crate::registers::Mode0::run_mode))
+/// so that each pulse on START triggers exactly one conversion on every device.
+///
+/// Devices can keep their own START and PWDN pins, e.g. to power the group down between bursts
+/// or to run a single device while the shared line stays low.
+pub struct SyncGroup<
+    SPI,
+    CS,
+    DRDY,
+    RST,
+    X,
+    LINE,
+    const N: usize,
+    START = StartNoConnection,
+    PWDN = PwdnNoConnection,
+> where
+    X: Sealed,
+{
+    devices: [Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>; N],
+    start: LINE,
+}
+
+impl<SPI, CS, DRDY, RST, X, LINE, START, PWDN, SpiErr, IoErr, const N: usize>
+    SyncGroup<SPI, CS, DRDY, RST, X, LINE, N, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    LINE: OutputPin<Error = IoErr>,
+    X: Sealed,
+{
+    /// Create a new group, START should be low.
+    pub fn new(devices: [Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>; N], start: LINE) -> Self {
+        Self { devices, start }
+    }
+
+    /// Release devices and START pin
+    #[allow(clippy::type_complexity)]
+    pub fn release(self) -> ([Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>; N], LINE) {
+        (self.devices, self.start)
+    }
+
+    /// Access the individual devices
+    pub fn devices(&mut self) -> &mut [Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>; N] {
+        &mut self.devices
+    }
+
+    /// Configure each device in turn
+    pub fn configure<F>(&mut self, mut f: F) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        F: FnMut(
+            &mut Ads126x<SPI, CS, DRDY, RST, X, START, PWDN>,
+        ) -> Result<(), Ads126xError<SpiErr, IoErr>>,
+    {
+        self.devices.iter_mut().try_for_each(&mut f)
+    }
+
+    /// Write the same register value to all devices
+    pub fn write_reg<REG>(&mut self, reg: REG) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        REG: Register,
+    {
+        let b = reg.into_byte();
+        self.configure(|dev| dev.write_reg(REG::from_byte(b)))
+    }
+
+    /// Pulse the shared START line, triggering a conversion on all devices
+    pub fn trigger<DELAY>(&mut self, mut delay: DELAY) -> Result<(), Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        pulse_start(&mut self.start, &mut delay).map_err(Ads126xError::IoErr)
+    }
+}
+
+impl<SPI, CS, DRDY, RST, X, LINE, START, PWDN, SpiErr, IoErr, const N: usize>
+    SyncGroup<SPI, CS, DRDY, RST, X, LINE, N, START, PWDN>
+where
+    SPI: Transfer<u8, Error = SpiErr> + Write<u8, Error = SpiErr>,
+    CS: OutputPin<Error = IoErr>,
+    DRDY: InputPin<Error = IoErr>,
+    LINE: OutputPin<Error = IoErr>,
+    X: Sealed,
+{
+    /// Trigger a conversion and read one result from each device, polling DRDY every `us` microseconds.
+    ///
+    /// **NOTE: ** Only available when DRDY implements embedded_hal [InputPin].
+    pub fn sample<DELAY>(
+        &mut self,
+        mut delay: DELAY,
+        us: u32,
+    ) -> Result<[Data; N], Ads126xError<SpiErr, IoErr>>
+    where
+        DELAY: DelayUs<u32>,
+    {
+        pulse_start(&mut self.start, &mut delay).map_err(Ads126xError::IoErr)?;
+
+        let mut data = [Data::default(); N];
+        for (dev, data) in self.devices.iter_mut().zip(data.iter_mut()) {
+            while !dev.drdy()? {
+                delay.delay_us(us);
+            }
+            *data = dev.read_adc1()?;
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::v2::OutputPin;
+
+    use super::SyncGroup;
+    use crate::{
+        registers::{InpMux, InpMuxMuxx, Mode0, Mode2, Mode2Dr, Mode2Gain},
+        sim::{NoDelay, SimAds126x, SimPin},
+        Ads1263,
+    };
+
+    /// START line shared by two simulated devices
+    struct SharedStart<'a>([SimPin<'a>; 2]);
+
+    impl OutputPin for SharedStart<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0.iter_mut().try_for_each(|p| p.set_low())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0.iter_mut().try_for_each(|p| p.set_high())
+        }
+    }
+
+    #[test]
+    fn test_sync_group() {
+        let sims = [SimAds126x::ads1263(), SimAds126x::ads1263()];
+        sims[0].set_input(InpMuxMuxx::Ain0, 100_000);
+        sims[1].set_input(InpMuxMuxx::Ain0, 200_000);
+        // Each device also holds its own START pin
+        let devices: [Ads1263<_, _, _, _, SimPin>; 2] = sims.each_ref().map(|sim| {
+            Ads1263::probe(sim.spi(), sim.cs(), sim.drdy(), sim.rst())
+                .unwrap()
+                .with_start(sim.start())
+        });
+        let mut group = SyncGroup::new(devices, SharedStart([sims[0].start(), sims[1].start()]));
+
+        // Pulse conversion mode, one conversion per START pulse
+        let mut mode0 = Mode0(0);
+        mode0.set_run_mode(true);
+        group.write_reg(mode0).unwrap();
+        let mut gains = [Mode2Gain::None, Mode2Gain::Gain2].into_iter();
+        group
+            .configure(|dev| {
+                let gain = gains.next().unwrap();
+                dev.write_reg(Mode2::with(Mode2Dr::Sps400, gain, false))?;
+                dev.write_reg(InpMux::singleended(InpMuxMuxx::Ain0))
+            })
+            .unwrap();
+        for sim in &sims {
+            assert!(sim.registers().get::<Mode0>().run_mode());
+            assert_eq!(sim.registers().get::<InpMux>().0, 0x0A);
+        }
+        assert_eq!(sims[1].registers().get::<Mode2>().pga_gain(), 2);
+
+        // Nothing converts until START is pulsed
+        assert!(group.devices().iter_mut().all(|dev| !dev.drdy().unwrap()));
+        group.trigger(NoDelay).unwrap();
+        assert!(sims.iter().all(|sim| sim.adc1_running()));
+        for dev in group.devices() {
+            assert!(dev.drdy().unwrap());
+            dev.read_adc1().unwrap();
+        }
+        assert!(sims.iter().all(|sim| !sim.adc1_running()));
+
+        let data = group.sample(NoDelay, 10).unwrap();
+        assert_eq!(data.map(|d| d.to_microvolts()), [100_000, 200_000]);
+        assert_eq!(data.map(|d| d.gain), [1, 2]);
+
+        let (mut devices, mut start) = group.release();
+        assert_eq!(devices[1].registers().get::<Mode2>().pga_gain(), 2);
+        devices[0].pulse_start(NoDelay).unwrap();
+        assert!(sims[0].adc1_running() && !sims[1].adc1_running());
+        devices[0].read_adc1().unwrap();
+        start.set_high().unwrap();
+        assert!(sims.iter().all(|sim| sim.adc1_running()));
+    }
+}
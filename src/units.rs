@@ -0,0 +1,83 @@
+//! Typed physical units using [uom]
+
+use uom::si::{
+    electric_current::{microampere, nanoampere},
+    electric_potential::nanovolt,
+    f64::{ElectricCurrent, ElectricPotential, ThermodynamicTemperature},
+    thermodynamic_temperature::degree_celsius,
+};
+
+use crate::{
+    device::Data,
+    registers::{IdacMag, IdacMagMagx, Mode1, Mode1SBMag},
+};
+
+impl Data {
+    /// Input voltage using the reference voltage and gain active during conversion
+    pub fn voltage(&self) -> ElectricPotential {
+        ElectricPotential::new::<nanovolt>(self.to_nanovolts() as f64)
+    }
+
+    /// Temperature of a reading of the internal temperature sensor, see [Data::to_millicelsius]
+    pub fn temperature(&self) -> ThermodynamicTemperature {
+        ThermodynamicTemperature::new::<degree_celsius>(f64::from(self.to_millicelsius()) / 1000.0)
+    }
+}
+
+impl IdacMagMagx {
+    /// IDAC current
+    pub fn current(&self) -> ElectricCurrent {
+        ElectricCurrent::new::<microampere>(f64::from(self.microamps()))
+    }
+}
+
+impl IdacMag {
+    /// IDAC1 current, `None` for reserved values
+    pub fn idac1_current(&self) -> Option<ElectricCurrent> {
+        self.idac1_ua()
+            .map(|ua| ElectricCurrent::new::<microampere>(f64::from(ua)))
+    }
+
+    /// IDAC2 current, `None` for reserved values
+    pub fn idac2_current(&self) -> Option<ElectricCurrent> {
+        self.idac2_ua()
+            .map(|ua| ElectricCurrent::new::<microampere>(f64::from(ua)))
+    }
+}
+
+impl Mode1SBMag {
+    /// Sensor bias current, `None` for the 10 MOhm bias resistor
+    pub fn current(&self) -> Option<ElectricCurrent> {
+        self.nanoamps()
+            .map(|na| ElectricCurrent::new::<nanoampere>(f64::from(na)))
+    }
+}
+
+impl Mode1 {
+    /// Sensor bias current, `None` for the 10 MOhm bias resistor or a reserved value
+    pub fn sensor_bias_current(&self) -> Option<ElectricCurrent> {
+        self.sensor_bias_na()
+            .map(|na| ElectricCurrent::new::<nanoampere>(f64::from(na)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si::{electric_current::microampere, electric_potential::millivolt};
+
+    use crate::{device::Data, registers::Mode1SBMag};
+
+    #[test]
+    fn test_units() {
+        let data = Data {
+            code: 1 << 30,
+            vref_uv: 2_500_000,
+            gain: 1,
+            ..Default::default()
+        };
+        assert_eq!(data.voltage().get::<millivolt>(), 1250.0);
+        let ua = Mode1SBMag::Ua0_5.current().unwrap().get::<microampere>();
+        assert!((ua - 0.5).abs() < 1e-9, "{ua}");
+        assert!(Mode1SBMag::Res.current().is_none());
+    }
+}